@@ -0,0 +1,60 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use progression_search::{CompoundTask, Method, PrimitiveAction, Task, HTN};
+
+fn action(name: &str) -> Rc<Task<u32>> {
+    Rc::new(Task::Primitive(PrimitiveAction::new(
+        name.to_string(),
+        HashSet::new(),
+        HashSet::new(),
+        HashSet::new(),
+    )))
+}
+
+// A compound task followed by a totally ordered chain of `size` actions. The
+// task's method introduces three partially ordered actions.
+fn chain(size: u32) -> HTN<u32> {
+    let method = Method::new(
+        "m".to_string(),
+        HTN::new(
+            HashSet::from([1, 2, 3]),
+            vec![(1, 3), (2, 3)],
+            HashMap::from([(1, action("a")), (2, action("b")), (3, action("c"))]),
+        ),
+    );
+    let task = Rc::new(Task::Compound(CompoundTask::new("t".to_string(), vec![method])));
+    let mut mappings = HashMap::from([(0, task)]);
+    let step = action("step");
+    for id in 1..=size {
+        mappings.insert(id, step.clone());
+    }
+    HTN::new((0..=size).collect(), (0..size).map(|x| (x, x + 1)).collect(), mappings)
+}
+
+fn successors(c: &mut Criterion) {
+    let mut group = c.benchmark_group("successors");
+    for size in [100, 1_000, 10_000] {
+        let network = chain(size);
+        let method = match network.get_task(0) {
+            Some(Task::Compound(c)) => Method::new("m".to_string(), c.methods()[0].decomposition.clone()),
+            _ => unreachable!(),
+        };
+        let expanded = network.decompose(0, &method).unwrap();
+        let first = *expanded.get_unconstrained_tasks().iter().next().unwrap();
+        group.bench_with_input(BenchmarkId::new("decompose", size), &network, |b, n| {
+            b.iter(|| n.decompose(0, &method))
+        });
+        group.bench_with_input(BenchmarkId::new("apply_action", size), &expanded, |b, n| {
+            b.iter(|| n.apply_action(first))
+        });
+        group.bench_with_input(BenchmarkId::new("unconstrained_tasks", size), &expanded, |b, n| {
+            b.iter(|| n.get_unconstrained_tasks())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, successors);
+criterion_main!(benches);
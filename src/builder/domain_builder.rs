@@ -0,0 +1,343 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::rc::Rc;
+
+use super::error::BuildError;
+use super::network_builder::NetworkBuilder;
+use crate::domain::Domain;
+use crate::task_network::{CompoundTask, ConditionalEffect, Method, MethodOwner, PrimitiveAction, Task, HTN};
+
+#[derive(Debug)]
+enum Declaration<T> {
+    Primitive {
+        pre_cond: HashSet<T>,
+        add_effects: HashSet<T>,
+        del_effects: HashSet<T>,
+    },
+    Compound,
+}
+
+/// Declares tasks and methods by name and assembles them into a validated
+/// domain or task network, so that no task ids have to be chosen by hand.
+///
+/// Tasks may be referred to before they are declared, which is what
+/// recursive domains need; all names are resolved by `build`.
+#[derive(Debug)]
+pub struct DomainBuilder<T: Hash + Eq> {
+    tasks: Vec<(String, Declaration<T>)>,
+    methods: Vec<(String, String, NetworkBuilder)>,
+    action_costs: HashMap<String, u32>,
+    negative_preconditions: HashMap<String, HashSet<T>>,
+    conditional_effects: HashMap<String, Vec<ConditionalEffect<T>>>,
+    method_costs: HashMap<(String, String), u32>,
+    method_preconditions: HashMap<(String, String), HashSet<T>>,
+}
+
+impl<T: Hash + Eq> Default for DomainBuilder<T> {
+    fn default() -> Self {
+        DomainBuilder {
+            tasks: Vec::new(),
+            methods: Vec::new(),
+            action_costs: HashMap::new(),
+            negative_preconditions: HashMap::new(),
+            conditional_effects: HashMap::new(),
+            method_costs: HashMap::new(),
+            method_preconditions: HashMap::new(),
+        }
+    }
+}
+
+impl<T: Hash + Eq> DomainBuilder<T> {
+    pub fn new() -> DomainBuilder<T> {
+        DomainBuilder::default()
+    }
+
+    pub fn primitive(
+        mut self,
+        name: &str,
+        pre_cond: impl IntoIterator<Item = T>,
+        add_effects: impl IntoIterator<Item = T>,
+        del_effects: impl IntoIterator<Item = T>,
+    ) -> DomainBuilder<T> {
+        let declaration = Declaration::Primitive {
+            pre_cond: pre_cond.into_iter().collect(),
+            add_effects: add_effects.into_iter().collect(),
+            del_effects: del_effects.into_iter().collect(),
+        };
+        self.tasks.push((name.to_string(), declaration));
+        self
+    }
+
+    pub fn compound(mut self, name: &str) -> DomainBuilder<T> {
+        self.tasks.push((name.to_string(), Declaration::Compound));
+        self
+    }
+
+    /// Adds a method decomposing the compound task `task` into `network`.
+    pub fn method(mut self, task: &str, name: &str, network: NetworkBuilder) -> DomainBuilder<T> {
+        self.methods.push((task.to_string(), name.to_string(), network));
+        self
+    }
+
+    /// Sets the cost of the primitive task `name`, which is 1 by default.
+    pub fn action_cost(mut self, name: &str, cost: u32) -> DomainBuilder<T> {
+        self.action_costs.insert(name.to_string(), cost);
+        self
+    }
+
+    /// Sets the facts that must not hold for the primitive task `name` to be
+    /// applicable.
+    pub fn negative_preconditions(mut self, name: &str, facts: impl IntoIterator<Item = T>) -> DomainBuilder<T> {
+        self.negative_preconditions.insert(name.to_string(), facts.into_iter().collect());
+        self
+    }
+
+    /// Adds a conditional effect to the primitive task `name`.
+    pub fn conditional_effect(mut self, name: &str, effect: ConditionalEffect<T>) -> DomainBuilder<T> {
+        self.conditional_effects.entry(name.to_string()).or_default().push(effect);
+        self
+    }
+
+    /// Sets the cost of the method `name` of `task`, which is 0 by default.
+    pub fn method_cost(mut self, task: &str, name: &str, cost: u32) -> DomainBuilder<T> {
+        self.method_costs.insert((task.to_string(), name.to_string()), cost);
+        self
+    }
+
+    /// Sets the facts that must hold for the method `name` of `task` to be
+    /// applied; methods have no preconditions by default.
+    pub fn method_preconditions(
+        mut self,
+        task: &str,
+        name: &str,
+        preconditions: impl IntoIterator<Item = T>,
+    ) -> DomainBuilder<T> {
+        self.method_preconditions
+            .insert((task.to_string(), name.to_string()), preconditions.into_iter().collect());
+        self
+    }
+
+    /// Resolves all names and checks the decomposition of every method.
+    pub fn domain(mut self) -> Result<Domain<T>, BuildError>
+    where
+        T: Clone,
+    {
+        let mut tasks: HashMap<String, Rc<Task<T>>> = HashMap::new();
+        for (name, declaration) in self.tasks {
+            let task = match declaration {
+                Declaration::Primitive {
+                    pre_cond,
+                    add_effects,
+                    del_effects,
+                } => {
+                    let cost = self.action_costs.remove(&name).unwrap_or(1);
+                    let negative = self.negative_preconditions.remove(&name).unwrap_or_default();
+                    let conditional = self.conditional_effects.remove(&name).unwrap_or_default();
+                    let action = PrimitiveAction::new(name.clone(), pre_cond, add_effects, del_effects)
+                        .with_negative_preconditions(negative)
+                        .with_conditional_effects(conditional)
+                        .with_cost(cost);
+                    Task::Primitive(action)
+                }
+                Declaration::Compound => Task::Compound(CompoundTask::new(name.clone(), Vec::new())),
+            };
+            if tasks.insert(name.clone(), Rc::new(task)).is_some() {
+                return Err(BuildError::DuplicateTask(name));
+            }
+        }
+        // Settings of primitive tasks have been taken out above
+        let mut configured = self
+            .action_costs
+            .into_keys()
+            .chain(self.negative_preconditions.into_keys())
+            .chain(self.conditional_effects.into_keys());
+        if let Some(name) = configured.next() {
+            if tasks.contains_key(&name) {
+                return Err(BuildError::NotPrimitive(name));
+            }
+            return Err(BuildError::UndefinedTask(name));
+        }
+        let mut methods = HashSet::new();
+        for (task, name, decomposition) in self.methods {
+            let key = (task.clone(), name.clone());
+            let decomposition = decomposition.assemble(&format!("method `{}`", name), |x| tasks.get(x))?;
+            let cost = self.method_costs.get(&key).copied().unwrap_or(0);
+            let preconditions = self.method_preconditions.get(&key).into_iter().flatten().cloned();
+            let method = Method::new(name.clone(), decomposition)
+                .with_preconditions(preconditions)
+                .with_cost(cost);
+            match tasks.get(&task).map(|x| x.as_ref()) {
+                Some(Task::Compound(c)) => c.add_method(method),
+                Some(Task::Primitive(_)) => {
+                    return Err(BuildError::PrimitiveMethodTask { method: name, task });
+                }
+                None => return Err(BuildError::UndefinedTask(task)),
+            }
+            methods.insert(key);
+        }
+        let mut configured = self.method_costs.into_keys().chain(self.method_preconditions.into_keys());
+        if let Some((_, name)) = configured.find(|x| !methods.contains(x)) {
+            return Err(BuildError::UndefinedMethod(name));
+        }
+        let owner = MethodOwner::new(tasks.values().cloned());
+        let domain = Domain::new(tasks.into_values())?.with_owner(Rc::new(owner));
+        domain.validate()?;
+        Ok(domain)
+    }
+
+    /// Builds the domain and returns `network` as the initial task network.
+    pub fn build(self, network: NetworkBuilder) -> Result<HTN<T>, BuildError>
+    where
+        T: Clone,
+    {
+        network.build(&self.domain()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::example::create_problem_instance;
+    use crate::search::{ProgressionSearch, SearchResult};
+    use crate::task_network::HtnError;
+
+    fn construction() -> DomainBuilder<u32> {
+        let mut domain = DomainBuilder::new();
+        for name in [
+            "ObtainPermit",
+            "HireBuilder",
+            "PayBuilder",
+            "BuildFoundation",
+            "BuildFrame",
+            "BuildRoof",
+            "BuildWalls",
+            "BuildInterior",
+        ] {
+            domain = domain.primitive(name, [], [], []);
+        }
+        let method = NetworkBuilder::new()
+            .task("foundation", "BuildFoundation")
+            .task("frame", "BuildFrame")
+            .task("roof", "BuildRoof")
+            .task("walls", "BuildWalls")
+            .task("interior", "BuildInterior")
+            .sequence(["foundation", "frame", "roof", "interior"])
+            .before("frame", "walls")
+            .before("walls", "interior");
+        domain.compound("Construct").method("Construct", "method-01", method)
+    }
+
+    fn construction_network() -> NetworkBuilder {
+        NetworkBuilder::new()
+            .task("permit", "ObtainPermit")
+            .task("hire", "HireBuilder")
+            .task("construct", "Construct")
+            .task("pay", "PayBuilder")
+            .sequence(["permit", "construct", "pay"])
+            .before("hire", "construct")
+    }
+
+    #[test]
+    fn construction_test() {
+        let htn = construction().build(construction_network()).unwrap();
+        assert!(HTN::is_isomorphic(&htn, &create_problem_instance()));
+        let Task::Compound(construct) = htn.get_task(3).unwrap() else {
+            panic!("`Construct` should be compound");
+        };
+        let method = &construct.methods()[0];
+        assert_eq!(method.name, "method-01");
+        assert_eq!(method.decomposition.count_tasks(), 5);
+        assert_eq!(method.decomposition.get_orderings().len(), 5);
+    }
+
+    #[test]
+    fn shared_method_name_test() {
+        let domain = DomainBuilder::<u32>::new()
+            .primitive("a", [], [], [])
+            .compound("s")
+            .compound("t")
+            .method("s", "m", NetworkBuilder::new().task("x", "a"))
+            .method("t", "m", NetworkBuilder::new().task("x", "a"))
+            .method_cost("t", "m", 3)
+            .method_preconditions("s", "m", [1])
+            .domain()
+            .unwrap();
+        let method = |task| match domain.get(task).map(|x| x.as_ref()) {
+            Some(Task::Compound(c)) => (c.methods()[0].cost, c.methods()[0].preconditions.clone()),
+            _ => panic!("`{}` should be compound", task),
+        };
+        assert_eq!(method("s"), (0, HashSet::from([1])));
+        assert_eq!(method("t"), (3, HashSet::new()));
+    }
+
+    #[test]
+    fn recursive_domain_test() {
+        let domain = DomainBuilder::new()
+            .compound("walk")
+            .primitive("step", [], ["moved"], [])
+            .method(
+                "walk",
+                "m-again",
+                NetworkBuilder::new().task("s", "step").task("w", "walk").sequence(["s", "w"]),
+            )
+            .method("walk", "m-done", NetworkBuilder::new().task("s", "step"));
+        let htn = domain.build(NetworkBuilder::new().task("w", "walk")).unwrap();
+        let mut search = ProgressionSearch::new();
+        match search.run(HashSet::new(), htn).unwrap() {
+            SearchResult::Solved { plan, .. } => assert_eq!(plan.action_names(), vec!["step"]),
+            x => panic!("unexpected result {:?}", x),
+        }
+    }
+
+    #[test]
+    fn error_test() {
+        let network = || NetworkBuilder::new().task("a", "a");
+        let domain = || DomainBuilder::<u32>::new().primitive("a", [], [], []);
+        assert_eq!(
+            domain().primitive("a", [], [], []).build(network()).err(),
+            Some(BuildError::DuplicateTask("a".to_string()))
+        );
+        assert_eq!(
+            domain().build(network().task("b", "b")).err(),
+            Some(BuildError::UndefinedTask("b".to_string()))
+        );
+        assert_eq!(
+            domain().method("a", "m", NetworkBuilder::new()).build(network()).err(),
+            Some(BuildError::PrimitiveMethodTask { method: "m".to_string(), task: "a".to_string() })
+        );
+        assert_eq!(
+            domain().build(network().task("a", "a")).err(),
+            Some(BuildError::DuplicateSubtask { network: "the initial network".to_string(), id: "a".to_string() })
+        );
+        assert_eq!(
+            domain().build(network().before("a", "x")).err(),
+            Some(BuildError::UndefinedSubtask { network: "the initial network".to_string(), id: "x".to_string() })
+        );
+        assert_eq!(
+            domain().build(network().task("b", "a").sequence(["a", "b", "a"])).err(),
+            Some(BuildError::Htn(HtnError::CyclicOrdering(1)))
+        );
+        assert_eq!(
+            domain().action_cost("b", 2).build(network()).err(),
+            Some(BuildError::UndefinedTask("b".to_string()))
+        );
+        assert_eq!(
+            domain().compound("c").action_cost("c", 2).build(network()).err(),
+            Some(BuildError::NotPrimitive("c".to_string()))
+        );
+        assert_eq!(
+            domain().compound("c").negative_preconditions("c", [1]).build(network()).err(),
+            Some(BuildError::NotPrimitive("c".to_string()))
+        );
+        assert_eq!(
+            domain().method_cost("a", "m", 2).build(network()).err(),
+            Some(BuildError::UndefinedMethod("m".to_string()))
+        );
+        assert_eq!(
+            domain().method_preconditions("a", "m", [1]).build(network()).err(),
+            Some(BuildError::UndefinedMethod("m".to_string()))
+        );
+        let result = domain().compound("c").build(network().task("c", "c"));
+        assert!(matches!(result, Err(BuildError::Htn(HtnError::CompoundWithoutMethods { .. }))));
+    }
+}
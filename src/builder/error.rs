@@ -0,0 +1,55 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::task_network::HtnError;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    DuplicateTask(String),
+    UndefinedTask(String),
+    UndefinedMethod(String),
+    /// A setting only primitive tasks have (a cost, negative preconditions or
+    /// conditional effects) was given for a compound task
+    NotPrimitive(String),
+    PrimitiveMethodTask { method: String, task: String },
+    DuplicateSubtask { network: String, id: String },
+    UndefinedSubtask { network: String, id: String },
+    /// The assembled network failed `HTN::validate`
+    Htn(HtnError),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::DuplicateTask(x) => write!(f, "`{}` is declared twice", x),
+            BuildError::UndefinedTask(x) => write!(f, "undefined task `{}`", x),
+            BuildError::UndefinedMethod(x) => write!(f, "undefined method `{}`", x),
+            BuildError::NotPrimitive(x) => write!(f, "`{}` is not a primitive task", x),
+            BuildError::PrimitiveMethodTask { method, task } => {
+                write!(f, "method `{}` decomposes the primitive task `{}`", method, task)
+            }
+            BuildError::DuplicateSubtask { network, id } => {
+                write!(f, "duplicate subtask id `{}` in {}", id, network)
+            }
+            BuildError::UndefinedSubtask { network, id } => {
+                write!(f, "ordering of {} refers to the undefined subtask `{}`", network, id)
+            }
+            BuildError::Htn(e) => e.fmt(f),
+        }
+    }
+}
+
+impl Error for BuildError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BuildError::Htn(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<HtnError> for BuildError {
+    fn from(e: HtnError) -> Self {
+        BuildError::Htn(e)
+    }
+}
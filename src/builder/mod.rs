@@ -0,0 +1,7 @@
+mod domain_builder;
+mod error;
+mod network_builder;
+
+pub use domain_builder::DomainBuilder;
+pub use error::BuildError;
+pub use network_builder::NetworkBuilder;
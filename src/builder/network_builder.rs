@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::rc::Rc;
+
+use super::error::BuildError;
+use crate::domain::Domain;
+use crate::task_network::{Task, HTN};
+
+/// The subtasks and orderings of a task network, referring to subtasks by
+/// local names and to tasks by their names in the domain.
+#[derive(Debug, Clone, Default)]
+pub struct NetworkBuilder {
+    subtasks: Vec<(String, String)>,
+    orderings: Vec<(String, String)>,
+}
+
+impl NetworkBuilder {
+    pub fn new() -> NetworkBuilder {
+        NetworkBuilder::default()
+    }
+
+    /// Adds the subtask `id`, an instance of the task `task`.
+    pub fn task(mut self, id: &str, task: &str) -> NetworkBuilder {
+        self.subtasks.push((id.to_string(), task.to_string()));
+        self
+    }
+
+    /// Orders subtask `before` ahead of subtask `after`.
+    pub fn before(mut self, before: &str, after: &str) -> NetworkBuilder {
+        self.orderings.push((before.to_string(), after.to_string()));
+        self
+    }
+
+    /// Orders each of the given subtasks ahead of the next one.
+    pub fn sequence<'a>(mut self, ids: impl IntoIterator<Item = &'a str>) -> NetworkBuilder {
+        let ids: Vec<&str> = ids.into_iter().collect();
+        for pair in ids.windows(2) {
+            self = self.before(pair[0], pair[1]);
+        }
+        self
+    }
+
+    /// Resolves the task names in the domain and returns the network,
+    /// checked with `HTN::validate`.
+    pub fn build<T: Hash + Eq>(&self, domain: &Domain<T>) -> Result<HTN<T>, BuildError> {
+        let mut htn = self.assemble("the initial network", |name| domain.get(name))?;
+        htn.validate()?;
+        if let Some(owner) = domain.owner() {
+            htn = htn.with_owner(Rc::clone(owner));
+        }
+        Ok(htn)
+    }
+
+    // Assigns ids to the subtasks in the order they were added, starting at 1
+    pub(crate) fn assemble<'a, T: Hash + Eq + 'a>(
+        &self,
+        network: &str,
+        tasks: impl Fn(&str) -> Option<&'a Rc<Task<T>>>,
+    ) -> Result<HTN<T>, BuildError> {
+        let mut ids = HashMap::new();
+        let mut mappings = HashMap::new();
+        for (id, task) in self.subtasks.iter() {
+            let task = tasks(task).ok_or_else(|| BuildError::UndefinedTask(task.clone()))?;
+            let next = ids.len() as u32 + 1;
+            if ids.insert(id.as_str(), next).is_some() {
+                return Err(BuildError::DuplicateSubtask {
+                    network: network.to_string(),
+                    id: id.clone(),
+                });
+            }
+            mappings.insert(next, Rc::clone(task));
+        }
+        let index = |id: &String| {
+            ids.get(id.as_str()).copied().ok_or_else(|| BuildError::UndefinedSubtask {
+                network: network.to_string(),
+                id: id.clone(),
+            })
+        };
+        let orderings = self
+            .orderings
+            .iter()
+            .map(|(a, b)| Ok((index(a)?, index(b)?)))
+            .collect::<Result<Vec<_>, BuildError>>()?;
+        Ok(HTN::new(ids.into_values().collect(), orderings, mappings))
+    }
+}
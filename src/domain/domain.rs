@@ -0,0 +1,190 @@
+use std::cell::Ref;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::rc::Rc;
+
+use crate::builder::BuildError;
+use crate::task_network::{CompoundTask, HtnError, Method, MethodOwner, PrimitiveAction, Task, HTN};
+
+/// The tasks of a planning domain together with their methods, independent
+/// of any problem. Tasks are numbered in the order of their names, so the
+/// ids do not depend on how the domain was put together.
+#[derive(Debug)]
+pub struct Domain<T: Hash + Eq> {
+    tasks: Vec<Rc<Task<T>>>,
+    ids: HashMap<String, usize>,
+    owner: Option<Rc<MethodOwner<T>>>,
+}
+
+impl<T: Hash + Eq> Domain<T> {
+    /// The domain made up of the given tasks and every task their methods
+    /// refer to, directly or indirectly. Tasks are told apart by name, so two
+    /// different tasks of the same name are a `BuildError::DuplicateTask`.
+    pub fn new(tasks: impl IntoIterator<Item = Rc<Task<T>>>) -> Result<Domain<T>, BuildError> {
+        let mut reachable: HashMap<String, Rc<Task<T>>> = HashMap::new();
+        let mut stack: Vec<Rc<Task<T>>> = tasks.into_iter().collect();
+        while let Some(task) = stack.pop() {
+            let name = task.get_name();
+            if let Some(seen) = reachable.get(&name) {
+                if !Rc::ptr_eq(seen, &task) {
+                    return Err(BuildError::DuplicateTask(name));
+                }
+                continue;
+            }
+            if let Task::Compound(c) = task.as_ref() {
+                for method in c.methods().iter() {
+                    stack.extend(method.decomposition.mapped_tasks().cloned());
+                }
+            }
+            reachable.insert(name, task);
+        }
+        Ok(Domain::from_tasks(reachable))
+    }
+
+    /// The domain of every task reachable from the network, keeping its
+    /// methods as long as the network would.
+    pub fn from_network(network: &HTN<T>) -> Domain<T> {
+        let mut domain = Domain::from_tasks(network.get_reachable_tasks());
+        domain.owner = network.owner().cloned();
+        domain
+    }
+
+    fn from_tasks(tasks: HashMap<String, Rc<Task<T>>>) -> Domain<T> {
+        let mut tasks: Vec<Rc<Task<T>>> = tasks.into_values().collect();
+        tasks.sort_by_key(|t| t.get_name());
+        let ids = tasks.iter().enumerate().map(|(i, t)| (t.get_name(), i)).collect();
+        Domain { tasks, ids, owner: None }
+    }
+
+    /// Holds on to the methods of recursive tasks, as `HTN::with_owner`
+    /// does; the networks built from the domain share the owner.
+    pub fn with_owner(mut self, owner: Rc<MethodOwner<T>>) -> Domain<T> {
+        self.owner = Some(owner);
+        self
+    }
+
+    pub fn owner(&self) -> Option<&Rc<MethodOwner<T>>> {
+        self.owner.as_ref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    pub fn task(&self, id: usize) -> Option<&Rc<Task<T>>> {
+        self.tasks.get(id)
+    }
+
+    pub fn task_id(&self, name: &str) -> Option<usize> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Rc<Task<T>>> {
+        self.task_id(name).map(|id| &self.tasks[id])
+    }
+
+    pub fn tasks(&self) -> impl Iterator<Item = (usize, &Rc<Task<T>>)> {
+        self.tasks.iter().enumerate()
+    }
+
+    pub fn actions(&self) -> impl Iterator<Item = (usize, &PrimitiveAction<T>)> {
+        self.tasks().filter_map(|(id, task)| match task.as_ref() {
+            Task::Primitive(a) => Some((id, a)),
+            Task::Compound(_) => None,
+        })
+    }
+
+    pub fn compound_tasks(&self) -> impl Iterator<Item = (usize, &CompoundTask<T>)> {
+        self.tasks().filter_map(|(id, task)| match task.as_ref() {
+            Task::Compound(c) => Some((id, c)),
+            Task::Primitive(_) => None,
+        })
+    }
+
+    /// The methods of the task, or `None` if it is not compound.
+    pub fn methods(&self, id: usize) -> Option<Ref<'_, Vec<Method<T>>>> {
+        match self.task(id)?.as_ref() {
+            Task::Compound(c) => Some(c.methods()),
+            Task::Primitive(_) => None,
+        }
+    }
+
+    /// Checks the decomposition of every method, as `HTN::validate` does for
+    /// the methods reachable from a network.
+    pub fn validate(&self) -> Result<(), HtnError> {
+        for (_, c) in self.compound_tasks() {
+            for method in c.methods().iter() {
+                method.decomposition.validate_network().map_err(|e| HtnError::InvalidMethod {
+                    task: c.name.clone(),
+                    method: method.name.clone(),
+                    error: Box::new(e),
+                })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::example::create_problem_instance;
+    use std::collections::HashSet;
+
+    #[test]
+    fn construction_domain_test() {
+        let domain = Domain::from_network(&create_problem_instance());
+        assert_eq!(domain.len(), 9);
+        assert_eq!(domain.actions().count(), 8);
+        let names: Vec<String> = domain.tasks().map(|(_, t)| t.get_name()).collect();
+        assert_eq!(names[0], "BuildFoundation");
+        let id = domain.task_id("Construct").unwrap();
+        assert_eq!(domain.compound_tasks().map(|(i, _)| i).collect::<Vec<_>>(), vec![id]);
+        assert_eq!(domain.methods(id).unwrap()[0].name, "method-01");
+        assert!(domain.methods(0).is_none());
+        assert_eq!(domain.get("PayBuilder").unwrap().get_name(), "PayBuilder");
+        assert!(domain.get("Missing").is_none());
+        assert_eq!(domain.validate(), Ok(()));
+    }
+
+    #[test]
+    fn invalid_method_test() {
+        let step: Rc<Task<u32>> = Rc::new(Task::Primitive(PrimitiveAction::new(
+            "step".to_string(),
+            HashSet::new(),
+            HashSet::new(),
+            HashSet::new(),
+        )));
+        let network = HTN::new(
+            HashSet::from([1, 2]),
+            vec![(1, 2), (2, 1)],
+            HashMap::from([(1, Rc::clone(&step)), (2, step)]),
+        );
+        let task = CompoundTask::new("walk".to_string(), vec![Method::new("m".to_string(), network)]);
+        let domain = Domain::new([Rc::new(Task::Compound(task))]).unwrap();
+        assert_eq!(domain.len(), 2);
+        assert!(matches!(domain.validate(), Err(HtnError::InvalidMethod { .. })));
+    }
+
+    #[test]
+    fn duplicate_task_test() {
+        let step = || -> Rc<Task<u32>> {
+            Rc::new(Task::Primitive(PrimitiveAction::new(
+                "step".to_string(),
+                HashSet::new(),
+                HashSet::new(),
+                HashSet::new(),
+            )))
+        };
+        let first = step();
+        assert_eq!(Domain::new([Rc::clone(&first), first]).unwrap().len(), 1);
+        assert_eq!(
+            Domain::new([step(), step()]).unwrap_err(),
+            BuildError::DuplicateTask("step".to_string())
+        );
+    }
+}
@@ -0,0 +1,6 @@
+#[allow(clippy::module_inception)]
+mod domain;
+mod problem;
+
+pub use domain::Domain;
+pub use problem::Problem;
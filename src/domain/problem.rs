@@ -0,0 +1,165 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::rc::Rc;
+
+use crate::task_network::{CompoundTask, Method, MethodOwner, PrimitiveAction, Task, HTN};
+
+/// A planning problem: refine the initial task network into actions
+/// executable from the initial state, reaching the goal if there is one.
+#[derive(Debug, Clone)]
+pub struct Problem<T: Hash + Eq> {
+    pub initial_state: HashSet<T>,
+    pub initial_network: HTN<T>,
+    /// Facts that must hold once every task has been done
+    pub goal: Option<HashSet<T>>,
+}
+
+impl<T: Hash + Eq> Problem<T> {
+    pub fn new(initial_state: HashSet<T>, initial_network: HTN<T>) -> Problem<T> {
+        Problem {
+            initial_state,
+            initial_network,
+            goal: None,
+        }
+    }
+
+    pub fn with_goal(mut self, goal: impl IntoIterator<Item = T>) -> Problem<T> {
+        self.goal = Some(goal.into_iter().collect());
+        self
+    }
+}
+
+impl<T: Hash + Eq + Clone> Problem<T> {
+    /// The problem under the HDDL semantics of method preconditions: every
+    /// method with preconditions gets a leading action
+    /// `__method_precondition_<method>`, ordered before all of its subtasks,
+    /// which requires them and has no effects and no cost. Since compound
+    /// tasks are decomposed before any action is applied, checking the
+    /// preconditions when decomposing rules out methods whose preconditions
+    /// are established by unordered tasks; compiled, they only have to hold
+    /// right before the method's first subtask.
+    pub fn compile_method_preconditions(&self) -> Problem<T> {
+        let mut compiler = Compiler {
+            tasks: HashMap::new(),
+            names: HashSet::new(),
+        };
+        let network = compiler.network(&self.initial_network);
+        let owner = MethodOwner::new(compiler.tasks.into_values());
+        Problem {
+            initial_state: self.initial_state.clone(),
+            initial_network: network.with_owner(Rc::new(owner)),
+            goal: self.goal.clone(),
+        }
+    }
+}
+
+struct Compiler<T: Hash + Eq> {
+    // Compiled tasks by name
+    tasks: HashMap<String, Rc<Task<T>>>,
+    // Names of the actions introduced so far
+    names: HashSet<String>,
+}
+
+impl<T: Hash + Eq + Clone> Compiler<T> {
+    // The network with every task compiled, keeping all ids
+    fn network(&mut self, network: &HTN<T>) -> HTN<T> {
+        let ids = network.get_task_ids();
+        let mappings = ids
+            .iter()
+            .filter_map(|id| network.mapping(*id).map(|task| (*id, self.task(task))))
+            .collect();
+        HTN::new(ids, network.get_orderings(), mappings)
+    }
+
+    fn task(&mut self, task: &Rc<Task<T>>) -> Rc<Task<T>> {
+        let name = task.get_name();
+        if let Some(compiled) = self.tasks.get(&name) {
+            return Rc::clone(compiled);
+        }
+        let Task::Compound(c) = task.as_ref() else {
+            self.tasks.insert(name, Rc::clone(task));
+            return Rc::clone(task);
+        };
+        // Registered before the methods are compiled, which may refer back to it
+        let compiled = Rc::new(Task::Compound(CompoundTask::new(name.clone(), Vec::new())));
+        self.tasks.insert(name, Rc::clone(&compiled));
+        for method in c.methods().iter() {
+            let mut network = self.network(&method.decomposition);
+            if !method.preconditions.is_empty() {
+                network = self.with_leading_action(network, method);
+            }
+            if let Task::Compound(target) = compiled.as_ref() {
+                target.add_method(Method::new(method.name.clone(), network).with_cost(method.cost));
+            }
+        }
+        compiled
+    }
+
+    fn with_leading_action(&mut self, network: HTN<T>, method: &Method<T>) -> HTN<T> {
+        // Ground methods share the name of their lifted method
+        let base = format!("__method_precondition_{}", method.name);
+        let mut name = base.clone();
+        let mut n = 1;
+        while !self.names.insert(name.clone()) {
+            n += 1;
+            name = format!("{}_{}", base, n);
+        }
+        let action = PrimitiveAction::new(name, method.preconditions.clone(), HashSet::new(), HashSet::new());
+        let id = network.next_id();
+        let mut ids = network.get_task_ids();
+        let mut orderings = network.get_orderings();
+        orderings.extend(ids.iter().map(|x| (id, *x)));
+        let mut mappings: HashMap<u32, Rc<Task<T>>> =
+            ids.iter().filter_map(|x| network.mapping(*x).map(|t| (*x, Rc::clone(t)))).collect();
+        mappings.insert(id, Rc::new(Task::Primitive(action.with_cost(0))));
+        ids.insert(id);
+        HTN::new(ids, orderings, mappings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::{DomainBuilder, NetworkBuilder};
+    use crate::search::{ProgressionSearch, SearchResult, SearchStrategy, TieBreaking};
+    use crate::verification::PlanVerifier;
+
+    #[test]
+    fn compile_method_preconditions_test() {
+        let network = DomainBuilder::new()
+            .primitive("get-car", [], ["car"], [])
+            .primitive("drive", [], [], [])
+            .primitive("walk", [], [], [])
+            .action_cost("walk", 5)
+            .compound("go")
+            .method("go", "m-drive", NetworkBuilder::new().task("d", "drive"))
+            .method_preconditions("go", "m-drive", ["car"])
+            .method("go", "m-walk", NetworkBuilder::new().task("w", "walk"))
+            .build(NetworkBuilder::new().task("c", "get-car").task("g", "go"))
+            .unwrap();
+        let original = Problem::new(HashSet::new(), network);
+        let problem = original.compile_method_preconditions();
+        let Some(Task::Compound(go)) = problem.initial_network.get_task(2) else {
+            panic!("`go` should be compound");
+        };
+        for method in go.methods().iter() {
+            assert!(method.preconditions.is_empty());
+            let expected = if method.name == "m-drive" { 2 } else { 1 };
+            assert_eq!(method.decomposition.count_tasks(), expected);
+        }
+
+        let mut search = ProgressionSearch::with_strategy(SearchStrategy::UniformCost, TieBreaking::Fifo);
+        let SearchResult::Solved { plan, cost } = search.solve(original).unwrap() else {
+            panic!("the problem should be solvable");
+        };
+        assert_eq!((plan.action_names(), cost), (vec!["get-car".to_string(), "walk".to_string()], 6));
+        // The car may be fetched after `go` has been decomposed
+        let SearchResult::Solved { plan, cost } = search.solve(problem.clone()).unwrap() else {
+            panic!("the compiled problem should be solvable");
+        };
+        assert_eq!(plan.action_names(), vec!["get-car", "__method_precondition_m-drive", "drive"]);
+        assert_eq!(cost, 2);
+        let verifier = PlanVerifier::new(&problem.initial_state, &problem.initial_network);
+        assert_eq!(verifier.verify_plan(&plan), Ok(()));
+    }
+}
@@ -0,0 +1,93 @@
+use std::error;
+use std::fmt;
+
+use crate::builder::BuildError;
+use crate::grounding::GroundingError;
+use crate::hddl::HddlError;
+use crate::json::JsonError;
+use crate::plan::PlanFormatError;
+use crate::task_network::HtnError;
+use crate::verification::VerificationError;
+
+/// Any error reported by this crate, so that callers combining several of
+/// its parts can propagate them with `?`.
+#[derive(Debug)]
+pub enum Error {
+    Htn(HtnError),
+    Hddl(HddlError),
+    Grounding(GroundingError),
+    Verification(VerificationError),
+    PlanFormat(PlanFormatError),
+    Build(BuildError),
+    Json(JsonError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Htn(e) => write!(f, "invalid task network: {}", e),
+            Error::Hddl(e) => e.fmt(f),
+            Error::Grounding(e) => write!(f, "grounding failed: {}", e),
+            Error::Verification(e) => write!(f, "invalid plan: {}", e),
+            Error::PlanFormat(e) => write!(f, "malformed plan at {}", e),
+            Error::Build(e) => write!(f, "invalid domain: {}", e),
+            Error::Json(e) => e.fmt(f),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Htn(e) => Some(e),
+            Error::Hddl(e) => Some(e),
+            Error::Grounding(e) => Some(e),
+            Error::Verification(e) => Some(e),
+            Error::PlanFormat(e) => Some(e),
+            Error::Build(e) => Some(e),
+            Error::Json(e) => Some(e),
+        }
+    }
+}
+
+impl From<HtnError> for Error {
+    fn from(e: HtnError) -> Self {
+        Error::Htn(e)
+    }
+}
+
+impl From<HddlError> for Error {
+    fn from(e: HddlError) -> Self {
+        Error::Hddl(e)
+    }
+}
+
+impl From<GroundingError> for Error {
+    fn from(e: GroundingError) -> Self {
+        Error::Grounding(e)
+    }
+}
+
+impl From<VerificationError> for Error {
+    fn from(e: VerificationError) -> Self {
+        Error::Verification(e)
+    }
+}
+
+impl From<PlanFormatError> for Error {
+    fn from(e: PlanFormatError) -> Self {
+        Error::PlanFormat(e)
+    }
+}
+
+impl From<BuildError> for Error {
+    fn from(e: BuildError) -> Self {
+        Error::Build(e)
+    }
+}
+
+impl From<JsonError> for Error {
+    fn from(e: JsonError) -> Self {
+        Error::Json(e)
+    }
+}
@@ -4,6 +4,7 @@ use std::collections::HashSet;
 use std::hash::Hash;
 use std::rc::Rc;
 
+#[allow(clippy::type_complexity)]
 fn create_initial_tasks() -> (Rc<Task<u32>>, Rc<Task<u32>>, Rc<Task<u32>>, Rc<Task<u32>>) {
     let empty = HashSet::new();
     let t1 = Task::Primitive(PrimitiveAction::new(
@@ -46,6 +47,7 @@ fn create_initial_tasks() -> (Rc<Task<u32>>, Rc<Task<u32>>, Rc<Task<u32>>, Rc<Ta
     (t1, t2, t3, t4)
 }
 
+#[allow(clippy::type_complexity)]
 fn decomposition_tasks<T: Eq + Hash + Clone>() -> (Task<T>, Task<T>, Task<T>, Task<T>, Task<T>) {
     let empty = HashSet::new();
     let t1 = Task::Primitive(PrimitiveAction::new(
@@ -0,0 +1,41 @@
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GroundingError {
+    UndefinedType(String),
+    UndefinedObject(String),
+    UndefinedTask(String),
+    UndefinedVariable { operator: String, variable: String },
+    UndefinedSubtask { network: String, id: String },
+    ArityMismatch { task: String, expected: usize, found: usize },
+    PrimitiveMethodTask { method: String, task: String },
+    TypeMismatch { task: String, object: String, expected: String },
+}
+
+impl fmt::Display for GroundingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GroundingError::UndefinedType(x) => write!(f, "undefined type `{}`", x),
+            GroundingError::UndefinedObject(x) => write!(f, "undefined object `{}`", x),
+            GroundingError::UndefinedTask(x) => write!(f, "undefined task `{}`", x),
+            GroundingError::UndefinedVariable { operator, variable } => {
+                write!(f, "`{}` uses the undeclared variable `{}`", operator, variable)
+            }
+            GroundingError::UndefinedSubtask { network, id } => {
+                write!(f, "ordering of `{}` refers to the undefined subtask `{}`", network, id)
+            }
+            GroundingError::ArityMismatch { task, expected, found } => {
+                write!(f, "`{}` expects {} arguments, found {}", task, expected, found)
+            }
+            GroundingError::PrimitiveMethodTask { method, task } => {
+                write!(f, "method `{}` decomposes the primitive task `{}`", method, task)
+            }
+            GroundingError::TypeMismatch { task, object, expected } => {
+                write!(f, "`{}` expects an object of type `{}`, found `{}`", task, expected, object)
+            }
+        }
+    }
+}
+
+impl Error for GroundingError {}
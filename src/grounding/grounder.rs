@@ -0,0 +1,799 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::rc::Rc;
+
+use super::error::GroundingError;
+use super::lifted::*;
+use super::objects::{Objects, TypedObjects};
+use crate::task_network::{CompoundTask, ConditionalEffect, Method, MethodOwner, PrimitiveAction, Task, HTN};
+
+/// Names a ground atom or task the way states and plans refer to it,
+/// e.g. `at truck-0 city-1`.
+pub fn ground_name(name: &str, args: &[String]) -> String {
+    let mut result = name.to_string();
+    for arg in args {
+        result.push(' ');
+        result.push_str(arg);
+    }
+    result
+}
+
+#[derive(Debug)]
+pub struct GroundProblem {
+    pub initial_state: HashSet<String>,
+    pub initial_network: HTN<String>,
+    pub goal: Option<HashSet<String>>,
+    /// Every ground task that survived pruning, by ground name; the methods
+    /// are kept as long as `initial_network`, or a network derived from it
+    pub tasks: HashMap<String, Rc<Task<String>>>,
+}
+
+/// Grounds a lifted domain and problem.
+///
+/// Actions are instantiated by a delete-relaxed reachability analysis from
+/// the initial state, and methods top-down from the initial task network;
+/// methods whose subtasks cannot be refined into reachable actions are pruned.
+pub fn ground(domain: &LiftedDomain, problem: &LiftedProblem) -> Result<GroundProblem, GroundingError> {
+    let objects = Objects::new(domain, problem)?;
+    Grounder::new(domain, &objects)?.run(problem)
+}
+
+#[derive(Debug, Clone)]
+enum Arg {
+    Var(usize),
+    Const(String),
+}
+
+#[derive(Debug)]
+struct Pattern {
+    name: String,
+    args: Vec<Arg>,
+}
+
+impl Pattern {
+    fn ground(&self, binding: &[String]) -> String {
+        let args: Vec<String> = self
+            .args
+            .iter()
+            .map(|x| match x {
+                Arg::Var(i) => binding[*i].clone(),
+                Arg::Const(c) => c.clone(),
+            })
+            .collect();
+        ground_name(&self.name, &args)
+    }
+}
+
+struct CompiledAction<'a> {
+    name: &'a str,
+    parameters: &'a [Parameter],
+    candidates: Vec<&'a TypedObjects>,
+    precondition: Vec<Pattern>,
+    equalities: Vec<(Arg, Arg)>,
+    negative_precondition: Vec<Pattern>,
+    inequalities: Vec<(Arg, Arg)>,
+    add_effects: Vec<Pattern>,
+    del_effects: Vec<Pattern>,
+    conditional_effects: Vec<CompiledConditionalEffect>,
+}
+
+struct CompiledConditionalEffect {
+    condition: Vec<Pattern>,
+    equalities: Vec<(Arg, Arg)>,
+    negative_condition: Vec<Pattern>,
+    inequalities: Vec<(Arg, Arg)>,
+    add_effects: Vec<Pattern>,
+    del_effects: Vec<Pattern>,
+}
+
+impl CompiledConditionalEffect {
+    // Whether the `=` atoms of the conditions allow the effect under `binding`
+    fn possible(&self, binding: &[String]) -> bool {
+        self.equalities.iter().all(|(x, y)| value(x, binding) == value(y, binding))
+            && self.inequalities.iter().all(|(x, y)| value(x, binding) != value(y, binding))
+    }
+}
+
+struct CompiledMethod<'a> {
+    name: &'a str,
+    candidates: Vec<&'a TypedObjects>,
+    task: Pattern,
+    precondition: Vec<Pattern>,
+    equalities: Vec<(Arg, Arg)>,
+    subtasks: Vec<Pattern>,
+    orderings: Vec<(usize, usize)>,
+}
+
+struct GroundMethod {
+    name: String,
+    task: String,
+    precondition: HashSet<String>,
+    subtasks: Vec<String>,
+    orderings: Vec<(usize, usize)>,
+}
+
+type FactIndex = HashMap<String, HashSet<Vec<String>>>;
+
+struct Grounder<'a> {
+    objects: &'a Objects,
+    actions: Vec<CompiledAction<'a>>,
+    methods: HashMap<&'a str, Vec<CompiledMethod<'a>>>,
+    arities: HashMap<&'a str, (usize, bool)>,
+}
+
+impl<'a> Grounder<'a> {
+    fn new(domain: &'a LiftedDomain, objects: &'a Objects) -> Result<Grounder<'a>, GroundingError> {
+        let mut arities = HashMap::new();
+        for action in domain.actions.iter() {
+            arities.insert(action.name.as_str(), (action.parameters.len(), true));
+        }
+        for task in domain.tasks.iter() {
+            for p in task.parameters.iter() {
+                objects.of_type(&p.type_name)?;
+            }
+            arities.insert(task.name.as_str(), (task.parameters.len(), false));
+        }
+        let mut grounder = Grounder {
+            objects,
+            actions: Vec::new(),
+            methods: HashMap::new(),
+            arities,
+        };
+        for action in domain.actions.iter() {
+            let compiled = grounder.compile_action(action)?;
+            grounder.actions.push(compiled);
+        }
+        for method in domain.methods.iter() {
+            let compiled = grounder.compile_method(method)?;
+            grounder.methods.entry(method.task.name.as_str()).or_default().push(compiled);
+        }
+        Ok(grounder)
+    }
+
+    fn candidates(&self, parameters: &'a [Parameter]) -> Result<Vec<&'a TypedObjects>, GroundingError> {
+        parameters.iter().map(|p| self.objects.of_type(&p.type_name)).collect()
+    }
+
+    fn compile_atom(&self, operator: &str, parameters: &[Parameter], atom: &LiftedAtom) -> Result<Pattern, GroundingError> {
+        let mut args = Vec::with_capacity(atom.args.len());
+        for arg in atom.args.iter() {
+            if arg.starts_with('?') {
+                let i = parameters.iter().position(|p| p.name == *arg).ok_or_else(|| {
+                    GroundingError::UndefinedVariable {
+                        operator: operator.to_string(),
+                        variable: arg.clone(),
+                    }
+                })?;
+                args.push(Arg::Var(i));
+            } else if self.objects.contains(arg) {
+                args.push(Arg::Const(arg.clone()));
+            } else {
+                return Err(GroundingError::UndefinedObject(arg.clone()));
+            }
+        }
+        Ok(Pattern {
+            name: atom.name.clone(),
+            args,
+        })
+    }
+
+    // Compiles a task reference, checking that the task exists with the right arity
+    fn compile_task(&self, operator: &str, parameters: &[Parameter], atom: &LiftedAtom) -> Result<(Pattern, bool), GroundingError> {
+        let (arity, primitive) = *self
+            .arities
+            .get(atom.name.as_str())
+            .ok_or_else(|| GroundingError::UndefinedTask(atom.name.clone()))?;
+        if arity != atom.args.len() {
+            return Err(GroundingError::ArityMismatch {
+                task: atom.name.clone(),
+                expected: arity,
+                found: atom.args.len(),
+            });
+        }
+        Ok((self.compile_atom(operator, parameters, atom)?, primitive))
+    }
+
+    fn compile_action(&self, action: &'a LiftedAction) -> Result<CompiledAction<'a>, GroundingError> {
+        let compile = |atoms: &[LiftedAtom]| -> Result<Vec<Pattern>, GroundingError> {
+            atoms
+                .iter()
+                .map(|x| self.compile_atom(&action.name, &action.parameters, x))
+                .collect()
+        };
+        let (precondition, equalities) = split_equalities(compile(&action.precondition)?);
+        let (negative_precondition, inequalities) = split_equalities(compile(&action.negative_precondition)?);
+        let mut conditional_effects = Vec::with_capacity(action.conditional_effects.len());
+        for effect in action.conditional_effects.iter() {
+            let (condition, equalities) = split_equalities(compile(&effect.condition)?);
+            let (negative_condition, inequalities) = split_equalities(compile(&effect.negative_condition)?);
+            conditional_effects.push(CompiledConditionalEffect {
+                condition,
+                equalities,
+                negative_condition,
+                inequalities,
+                add_effects: compile(&effect.add_effects)?,
+                del_effects: compile(&effect.del_effects)?,
+            });
+        }
+        Ok(CompiledAction {
+            name: &action.name,
+            parameters: &action.parameters,
+            candidates: self.candidates(&action.parameters)?,
+            precondition,
+            equalities,
+            negative_precondition,
+            inequalities,
+            add_effects: compile(&action.add_effects)?,
+            del_effects: compile(&action.del_effects)?,
+            conditional_effects,
+        })
+    }
+
+    fn compile_method(&self, method: &'a LiftedMethod) -> Result<CompiledMethod<'a>, GroundingError> {
+        let (task, primitive) = self.compile_task(&method.name, &method.parameters, &method.task)?;
+        if primitive {
+            return Err(GroundingError::PrimitiveMethodTask {
+                method: method.name.clone(),
+                task: method.task.name.clone(),
+            });
+        }
+        let precondition = method
+            .precondition
+            .iter()
+            .map(|x| self.compile_atom(&method.name, &method.parameters, x))
+            .collect::<Result<Vec<Pattern>, GroundingError>>()?;
+        let (precondition, equalities) = split_equalities(precondition);
+        let mut subtasks = Vec::with_capacity(method.subtasks.len());
+        for subtask in method.subtasks.iter() {
+            subtasks.push(self.compile_task(&method.name, &method.parameters, &subtask.task)?.0);
+        }
+        Ok(CompiledMethod {
+            name: &method.name,
+            candidates: self.candidates(&method.parameters)?,
+            task,
+            precondition,
+            equalities,
+            subtasks,
+            orderings: subtask_orderings(&method.name, &method.subtasks, &method.orderings)?,
+        })
+    }
+
+    fn run(&self, problem: &LiftedProblem) -> Result<GroundProblem, GroundingError> {
+        let mut facts: FactIndex = HashMap::new();
+        let mut initial_state = HashSet::new();
+        for fact in problem.init.iter() {
+            let pattern = self.compile_atom("init", &[], fact)?;
+            facts.entry(fact.name.clone()).or_default().insert(fact.args.clone());
+            initial_state.insert(pattern.ground(&[]));
+        }
+        let mut goal = None;
+        if let Some(atoms) = problem.goal.as_ref() {
+            let mut facts = HashSet::new();
+            for fact in atoms.iter() {
+                facts.insert(self.compile_atom("goal", &[], fact)?.ground(&[]));
+            }
+            goal = Some(facts);
+        }
+        let mut actions = self.reachable_actions(&mut facts);
+
+        // Top-down instantiation of the methods, starting from the initial network
+        let mut initial_subtasks = Vec::with_capacity(problem.subtasks.len());
+        let mut compounds: HashSet<String> = HashSet::new();
+        let mut queue = VecDeque::new();
+        for subtask in problem.subtasks.iter() {
+            let (pattern, primitive) = self.compile_task("initial network", &[], &subtask.task)?;
+            let name = pattern.ground(&[]);
+            if primitive {
+                let action = self.actions.iter().find(|a| a.name == pattern.name).unwrap();
+                for ((p, objects), arg) in action.parameters.iter().zip(action.candidates.iter()).zip(subtask.task.args.iter()) {
+                    if !objects.set.contains(arg) {
+                        return Err(GroundingError::TypeMismatch {
+                            task: pattern.name.clone(),
+                            object: arg.clone(),
+                            expected: p.type_name.clone(),
+                        });
+                    }
+                }
+                // Unreachable actions are kept so that the initial network stays intact
+                if !actions.contains_key(&name) {
+                    actions.insert(name.clone(), self.ground_action(action, &subtask.task.args));
+                }
+            } else if compounds.insert(name.clone()) {
+                queue.push_back((pattern.name.clone(), subtask.task.args.clone()));
+            }
+            initial_subtasks.push(name);
+        }
+        let mut ground_methods = Vec::new();
+        while let Some((task, args)) = queue.pop_front() {
+            for method in self.methods.get(task.as_str()).into_iter().flatten() {
+                for binding in self.method_bindings(method, &args, &facts) {
+                    let subtasks: Vec<String> = method.subtasks.iter().map(|s| s.ground(&binding)).collect();
+                    let refinable = method.subtasks.iter().zip(subtasks.iter()).all(|(pattern, name)| {
+                        !self.arities[pattern.name.as_str()].1 || actions.contains_key(name)
+                    });
+                    if !refinable {
+                        continue;
+                    }
+                    for (pattern, name) in method.subtasks.iter().zip(subtasks.iter()) {
+                        if !self.arities[pattern.name.as_str()].1 && compounds.insert(name.clone()) {
+                            let args = pattern.args.iter().map(|x| match x {
+                                Arg::Var(i) => binding[*i].clone(),
+                                Arg::Const(c) => c.clone(),
+                            });
+                            queue.push_back((pattern.name.clone(), args.collect()));
+                        }
+                    }
+                    ground_methods.push(GroundMethod {
+                        name: ground_name(method.name, &binding),
+                        task: method.task.ground(&binding),
+                        precondition: method.precondition.iter().map(|x| x.ground(&binding)).collect(),
+                        subtasks,
+                        orderings: method.orderings.clone(),
+                    });
+                }
+            }
+        }
+
+        // Bottom-up pruning of compound tasks that cannot be refined at all
+        let mut solvable: HashSet<&str> = HashSet::new();
+        let usable = |solvable: &HashSet<&str>, m: &GroundMethod| {
+            m.subtasks
+                .iter()
+                .all(|s| actions.contains_key(s) || solvable.contains(s.as_str()))
+        };
+        loop {
+            let mut changed = false;
+            for m in ground_methods.iter() {
+                if !solvable.contains(m.task.as_str()) && usable(&solvable, m) {
+                    solvable.insert(&m.task);
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        let mut tasks: HashMap<String, Rc<Task<String>>> = HashMap::new();
+        for name in compounds.iter() {
+            if solvable.contains(name.as_str()) || initial_subtasks.contains(name) {
+                let task = Task::Compound(CompoundTask::new(name.clone(), Vec::new()));
+                tasks.insert(name.clone(), Rc::new(task));
+            }
+        }
+        let mut task = |name: &String| -> Rc<Task<String>> {
+            match tasks.get(name) {
+                Some(x) => Rc::clone(x),
+                None => {
+                    let task = Rc::new(Task::Primitive(actions[name].clone()));
+                    tasks.insert(name.clone(), Rc::clone(&task));
+                    task
+                }
+            }
+        };
+        let mut methods = Vec::new();
+        for m in ground_methods.iter().filter(|m| usable(&solvable, m)) {
+            let subtasks: Vec<Rc<Task<String>>> = m.subtasks.iter().map(&mut task).collect();
+            let method = Method::new(m.name.clone(), network(subtasks, &m.orderings));
+            methods.push((task(&m.task), method.with_preconditions(m.precondition.iter().cloned())));
+        }
+        for (task, method) in methods {
+            if let Task::Compound(c) = task.as_ref() {
+                c.add_method(method);
+            }
+        }
+        let orderings = subtask_orderings("initial network", &problem.subtasks, &problem.orderings)?;
+        let initial_network = network(initial_subtasks.iter().map(&mut task).collect(), &orderings)
+            .with_owner(Rc::new(MethodOwner::new(tasks.values().cloned())));
+        Ok(GroundProblem {
+            initial_state,
+            initial_network,
+            goal,
+            tasks,
+        })
+    }
+
+    // Delete-relaxed fixpoint over the actions, returning every reachable
+    // ground action by name and leaving the reachable facts; negative
+    // preconditions and conditions are relaxed away like the deletes they
+    // depend on
+    fn reachable_actions(&self, facts: &mut FactIndex) -> HashMap<String, PrimitiveAction<String>> {
+        let mut result = HashMap::new();
+        loop {
+            let mut new_facts = Vec::new();
+            for action in self.actions.iter() {
+                for binding in self.action_bindings(action, facts) {
+                    let name = ground_name(action.name, &binding);
+                    if result.contains_key(&name) {
+                        continue;
+                    }
+                    let conditional = action
+                        .conditional_effects
+                        .iter()
+                        .filter(|e| e.possible(&binding))
+                        .flat_map(|e| e.add_effects.iter());
+                    for effect in action.add_effects.iter().chain(conditional) {
+                        let args: Vec<String> = effect
+                            .args
+                            .iter()
+                            .map(|x| match x {
+                                Arg::Var(i) => binding[*i].clone(),
+                                Arg::Const(c) => c.clone(),
+                            })
+                            .collect();
+                        new_facts.push((effect.name.clone(), args));
+                    }
+                    result.insert(name, self.ground_action(action, &binding));
+                }
+            }
+            let mut changed = false;
+            for (name, args) in new_facts {
+                changed |= facts.entry(name).or_default().insert(args);
+            }
+            if !changed {
+                break;
+            }
+        }
+        result
+    }
+
+    fn ground_action(&self, action: &CompiledAction, binding: &[String]) -> PrimitiveAction<String> {
+        let ground = |patterns: &[Pattern]| patterns.iter().map(|x| x.ground(binding)).collect();
+        let conditional_effects = action.conditional_effects.iter().filter(|e| e.possible(binding)).map(|e| {
+            ConditionalEffect::new(ground(&e.condition), ground(&e.add_effects), ground(&e.del_effects))
+                .with_negative_condition(e.negative_condition.iter().map(|x| x.ground(binding)))
+        });
+        PrimitiveAction::new(
+            ground_name(action.name, binding),
+            ground(&action.precondition),
+            ground(&action.add_effects),
+            ground(&action.del_effects),
+        )
+        .with_negative_preconditions(action.negative_precondition.iter().map(|x| x.ground(binding)))
+        .with_conditional_effects(conditional_effects)
+    }
+
+    fn action_bindings(&self, action: &CompiledAction, facts: &FactIndex) -> Vec<Vec<String>> {
+        let mut result = Vec::new();
+        let mut binding = vec![None; action.candidates.len()];
+        let preconditions: Vec<&Pattern> = action.precondition.iter().collect();
+        match_facts(&preconditions, facts, &action.candidates, &mut binding, &mut |b| {
+            complete(&action.candidates, b, 0, &mut |full| {
+                if action.equalities.iter().all(|(x, y)| value(x, full) == value(y, full))
+                    && action.inequalities.iter().all(|(x, y)| value(x, full) != value(y, full))
+                {
+                    result.push(full.to_vec());
+                }
+            })
+        });
+        result
+    }
+
+    // Methods whose preconditions cannot be reached are never applicable
+    fn method_bindings(&self, method: &CompiledMethod, args: &[String], facts: &FactIndex) -> Vec<Vec<String>> {
+        let mut binding = vec![None; method.candidates.len()];
+        let mut result = Vec::new();
+        if unify(&method.task, args, &method.candidates, &mut binding).is_some() {
+            let preconditions: Vec<&Pattern> = method.precondition.iter().collect();
+            match_facts(&preconditions, facts, &method.candidates, &mut binding, &mut |b| {
+                complete(&method.candidates, b, 0, &mut |full| {
+                    if method.equalities.iter().all(|(x, y)| value(x, full) == value(y, full)) {
+                        result.push(full.to_vec());
+                    }
+                })
+            });
+        }
+        result
+    }
+}
+
+// Separates the `=` atoms from the facts of a precondition
+fn split_equalities(patterns: Vec<Pattern>) -> (Vec<Pattern>, Vec<(Arg, Arg)>) {
+    let mut precondition = Vec::new();
+    let mut equalities = Vec::new();
+    for pattern in patterns {
+        match (pattern.name.as_str(), pattern.args.as_slice()) {
+            ("=", [a, b]) => equalities.push((a.clone(), b.clone())),
+            _ => precondition.push(pattern),
+        }
+    }
+    (precondition, equalities)
+}
+
+fn value<'b>(arg: &'b Arg, binding: &'b [String]) -> &'b str {
+    match arg {
+        Arg::Var(i) => &binding[*i],
+        Arg::Const(c) => c,
+    }
+}
+
+// Binds the pattern's variables to `args`, returning the newly bound indices
+fn unify(pattern: &Pattern, args: &[String], candidates: &[&TypedObjects], binding: &mut [Option<String>]) -> Option<Vec<usize>> {
+    let mut bound = Vec::new();
+    for (arg, val) in pattern.args.iter().zip(args) {
+        let ok = match arg {
+            Arg::Const(c) => c == val,
+            Arg::Var(i) => match &binding[*i] {
+                Some(x) => x == val,
+                None if candidates[*i].set.contains(val) => {
+                    binding[*i] = Some(val.clone());
+                    bound.push(*i);
+                    true
+                }
+                None => false,
+            },
+        };
+        if !ok {
+            for i in bound {
+                binding[i] = None;
+            }
+            return None;
+        }
+    }
+    Some(bound)
+}
+
+// Backtracks over the facts matching each precondition in turn
+fn match_facts(
+    patterns: &[&Pattern],
+    facts: &FactIndex,
+    candidates: &[&TypedObjects],
+    binding: &mut Vec<Option<String>>,
+    found: &mut dyn FnMut(&mut Vec<Option<String>>),
+) {
+    let Some((first, rest)) = patterns.split_first() else {
+        found(binding);
+        return;
+    };
+    for args in facts.get(&first.name).into_iter().flatten() {
+        if let Some(bound) = unify(first, args, candidates, binding) {
+            match_facts(rest, facts, candidates, binding, found);
+            for i in bound {
+                binding[i] = None;
+            }
+        }
+    }
+}
+
+// Enumerates the typed objects for every parameter left unbound
+fn complete(candidates: &[&TypedObjects], binding: &mut Vec<Option<String>>, i: usize, found: &mut dyn FnMut(&[String])) {
+    if i == binding.len() {
+        let full: Vec<String> = binding.iter().map(|x| x.clone().unwrap()).collect();
+        found(&full);
+    } else if binding[i].is_some() {
+        complete(candidates, binding, i + 1, found);
+    } else {
+        for object in candidates[i].list.iter() {
+            binding[i] = Some(object.clone());
+            complete(candidates, binding, i + 1, found);
+        }
+        binding[i] = None;
+    }
+}
+
+fn subtask_orderings(
+    network: &str,
+    subtasks: &[LiftedSubtask],
+    orderings: &[(String, String)],
+) -> Result<Vec<(usize, usize)>, GroundingError> {
+    let index = |id: &String| {
+        subtasks.iter().position(|s| s.id == *id).ok_or_else(|| GroundingError::UndefinedSubtask {
+            network: network.to_string(),
+            id: id.clone(),
+        })
+    };
+    orderings.iter().map(|(a, b)| Ok((index(a)?, index(b)?))).collect()
+}
+
+fn network(subtasks: Vec<Rc<Task<String>>>, orderings: &[(usize, usize)]) -> HTN<String> {
+    let ids = 1..=subtasks.len() as u32;
+    HTN::new(
+        ids.clone().collect(),
+        orderings.iter().map(|(a, b)| (*a as u32 + 1, *b as u32 + 1)).collect(),
+        ids.zip(subtasks).collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::{ProgressionSearch, SearchResult};
+
+    fn action(name: &str, params: &[(&str, &str)], pre: &[LiftedAtom], add: &[LiftedAtom], del: &[LiftedAtom]) -> LiftedAction {
+        LiftedAction {
+            name: name.to_string(),
+            parameters: params.iter().map(|(n, t)| Parameter::new(n, t)).collect(),
+            precondition: pre.to_vec(),
+            negative_precondition: Vec::new(),
+            add_effects: add.to_vec(),
+            del_effects: del.to_vec(),
+            conditional_effects: Vec::new(),
+        }
+    }
+
+    fn method(name: &str, params: &[(&str, &str)], task: LiftedAtom, subtasks: &[LiftedAtom]) -> LiftedMethod {
+        let subtasks: Vec<LiftedSubtask> = subtasks
+            .iter()
+            .enumerate()
+            .map(|(i, t)| LiftedSubtask { id: format!("t{}", i), task: t.clone() })
+            .collect();
+        let orderings = subtasks.windows(2).map(|w| (w[0].id.clone(), w[1].id.clone())).collect();
+        LiftedMethod {
+            name: name.to_string(),
+            parameters: params.iter().map(|(n, t)| Parameter::new(n, t)).collect(),
+            task,
+            precondition: Vec::new(),
+            subtasks,
+            orderings,
+        }
+    }
+
+    fn transport_domain() -> LiftedDomain {
+        let a = LiftedAtom::new;
+        let task = |name: &str, params: &[(&str, &str)]| LiftedTask {
+            name: name.to_string(),
+            parameters: params.iter().map(|(n, t)| Parameter::new(n, t)).collect(),
+        };
+        LiftedDomain {
+            types: Vec::from([
+                ("location".to_string(), "object".to_string()),
+                ("locatable".to_string(), "object".to_string()),
+                ("vehicle".to_string(), "locatable".to_string()),
+                ("package".to_string(), "locatable".to_string()),
+            ]),
+            constants: Vec::new(),
+            tasks: Vec::from([
+                task("deliver", &[("?p", "package"), ("?l", "location")]),
+                task("get-to", &[("?v", "vehicle"), ("?l", "location")]),
+            ]),
+            actions: Vec::from([
+                action(
+                    "drive",
+                    &[("?v", "vehicle"), ("?l1", "location"), ("?l2", "location")],
+                    &[a("at", &["?v", "?l1"]), a("road", &["?l1", "?l2"])],
+                    &[a("at", &["?v", "?l2"])],
+                    &[a("at", &["?v", "?l1"])],
+                ),
+                action(
+                    "pick-up",
+                    &[("?v", "vehicle"), ("?l", "location"), ("?p", "package")],
+                    &[a("at", &["?v", "?l"]), a("at", &["?p", "?l"])],
+                    &[a("in", &["?p", "?v"])],
+                    &[a("at", &["?p", "?l"])],
+                ),
+                action(
+                    "drop",
+                    &[("?v", "vehicle"), ("?l", "location"), ("?p", "package")],
+                    &[a("at", &["?v", "?l"]), a("in", &["?p", "?v"])],
+                    &[a("at", &["?p", "?l"])],
+                    &[a("in", &["?p", "?v"])],
+                ),
+            ]),
+            methods: Vec::from([
+                method(
+                    "m-deliver",
+                    &[("?p", "package"), ("?l1", "location"), ("?l2", "location"), ("?v", "vehicle")],
+                    a("deliver", &["?p", "?l2"]),
+                    &[
+                        a("get-to", &["?v", "?l1"]),
+                        a("pick-up", &["?v", "?l1", "?p"]),
+                        a("get-to", &["?v", "?l2"]),
+                        a("drop", &["?v", "?l2", "?p"]),
+                    ],
+                ),
+                method(
+                    "m-drive-to",
+                    &[("?v", "vehicle"), ("?l1", "location"), ("?l2", "location")],
+                    a("get-to", &["?v", "?l2"]),
+                    &[a("drive", &["?v", "?l1", "?l2"])],
+                ),
+                method(
+                    "m-i-am-there",
+                    &[("?v", "vehicle"), ("?l", "location")],
+                    a("get-to", &["?v", "?l"]),
+                    &[],
+                ),
+            ]),
+        }
+    }
+
+    fn transport_problem() -> LiftedProblem {
+        let objects = [("city-a", "location"), ("city-b", "location"), ("city-c", "location"), ("truck", "vehicle"), ("pkg", "package")];
+        LiftedProblem {
+            objects: objects.iter().map(|(o, t)| (o.to_string(), t.to_string())).collect(),
+            init: Vec::from([
+                LiftedAtom::new("road", &["city-a", "city-b"]),
+                LiftedAtom::new("road", &["city-b", "city-a"]),
+                LiftedAtom::new("at", &["truck", "city-a"]),
+                LiftedAtom::new("at", &["pkg", "city-b"]),
+            ]),
+            subtasks: Vec::from([LiftedSubtask {
+                id: "t0".to_string(),
+                task: LiftedAtom::new("deliver", &["pkg", "city-a"]),
+            }]),
+            orderings: Vec::new(),
+            goal: None,
+        }
+    }
+
+    #[test]
+    fn reachability_pruning_test() {
+        let result = ground(&transport_domain(), &transport_problem()).unwrap();
+        assert_eq!(result.initial_state.len(), 4);
+        assert!(result.initial_state.contains("at truck city-a"));
+        assert!(result.tasks.contains_key("drive truck city-a city-b"));
+        assert!(result.tasks.contains_key("pick-up truck city-b pkg"));
+        // city-c is not connected to the other locations
+        assert!(!result.tasks.contains_key("drive truck city-a city-c"));
+        assert!(!result.tasks.contains_key("pick-up truck city-c pkg"));
+        assert!(!result.tasks.contains_key("get-to truck city-c"));
+        if let Task::Compound(c) = result.tasks["deliver pkg city-a"].as_ref() {
+            // picking up at city-a or city-b, but not at city-c
+            let mut names: Vec<String> = c.methods().iter().map(|m| m.name.clone()).collect();
+            names.sort();
+            assert_eq!(names, vec!["m-deliver pkg city-a city-a truck", "m-deliver pkg city-b city-a truck"]);
+        } else {
+            panic!("expected a compound task");
+        }
+        if let Task::Compound(c) = result.tasks["get-to truck city-b"].as_ref() {
+            assert_eq!(c.methods().len(), 2);
+        } else {
+            panic!("expected a compound task");
+        }
+    }
+
+    #[test]
+    fn ground_search_test() {
+        let result = ground(&transport_domain(), &transport_problem()).unwrap();
+        let mut search = ProgressionSearch::new();
+        match search.run(result.initial_state, result.initial_network).unwrap() {
+            SearchResult::Solved { plan, .. } => assert_eq!(
+                plan.action_names(),
+                vec!["drive truck city-a city-b", "pick-up truck city-b pkg", "drive truck city-b city-a", "drop truck city-a pkg"]
+            ),
+            x => panic!("expected a solution, got {:?}", x),
+        }
+    }
+
+    #[test]
+    fn validation_test() {
+        let mut domain = transport_domain();
+        domain.methods[1].subtasks[0].task.args[0] = "?x".to_string();
+        assert_eq!(
+            ground(&domain, &transport_problem()).unwrap_err(),
+            GroundingError::UndefinedVariable { operator: "m-drive-to".to_string(), variable: "?x".to_string() }
+        );
+        let mut problem = transport_problem();
+        problem.subtasks[0].task.args.pop();
+        assert_eq!(
+            ground(&transport_domain(), &problem).unwrap_err(),
+            GroundingError::ArityMismatch { task: "deliver".to_string(), expected: 2, found: 1 }
+        );
+        let mut problem = transport_problem();
+        problem.subtasks[0].task = LiftedAtom::new("drive", &["pkg", "city-a", "city-b"]);
+        assert_eq!(
+            ground(&transport_domain(), &problem).unwrap_err(),
+            GroundingError::TypeMismatch { task: "drive".to_string(), object: "pkg".to_string(), expected: "vehicle".to_string() }
+        );
+    }
+
+    #[test]
+    fn method_precondition_test() {
+        let mut domain = transport_domain();
+        domain.methods[0].precondition.push(LiftedAtom::new("at", &["?p", "?l1"]));
+        // The truck never gets to city-c
+        domain.methods[2].precondition.push(LiftedAtom::new("at", &["?v", "?l"]));
+        let result = ground(&domain, &transport_problem()).unwrap();
+        if let Task::Compound(c) = result.tasks["deliver pkg city-a"].as_ref() {
+            let mut preconditions: Vec<String> = c.methods().iter().flat_map(|m| m.preconditions.clone()).collect();
+            preconditions.sort();
+            assert_eq!(preconditions, vec!["at pkg city-a", "at pkg city-b"]);
+        } else {
+            panic!("expected a compound task");
+        }
+        let mut search = ProgressionSearch::new();
+        match search.run(result.initial_state, result.initial_network).unwrap() {
+            SearchResult::Solved { plan, .. } => assert_eq!(plan.len(), 4),
+            x => panic!("expected a solution, got {:?}", x),
+        }
+    }
+}
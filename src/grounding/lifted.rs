@@ -0,0 +1,99 @@
+/// A predicate or task applied to arguments; arguments starting with `?` are
+/// variables, everything else is a constant.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LiftedAtom {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+impl LiftedAtom {
+    pub fn new(name: &str, args: &[&str]) -> LiftedAtom {
+        LiftedAtom {
+            name: name.to_string(),
+            args: args.iter().map(|x| x.to_string()).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Parameter {
+    pub name: String,
+    pub type_name: String,
+}
+
+impl Parameter {
+    pub fn new(name: &str, type_name: &str) -> Parameter {
+        Parameter {
+            name: name.to_string(),
+            type_name: type_name.to_string(),
+        }
+    }
+}
+
+/// A primitive operator; `=` atoms in the precondition, and in the negative
+/// one, constrain the instantiations and are not kept in the ground action.
+#[derive(Debug, Clone)]
+pub struct LiftedAction {
+    pub name: String,
+    pub parameters: Vec<Parameter>,
+    pub precondition: Vec<LiftedAtom>,
+    pub negative_precondition: Vec<LiftedAtom>,
+    pub add_effects: Vec<LiftedAtom>,
+    pub del_effects: Vec<LiftedAtom>,
+    pub conditional_effects: Vec<LiftedConditionalEffect>,
+}
+
+/// Effects that take place when the condition holds before the action;
+/// `=` atoms in the conditions are decided when grounding.
+#[derive(Debug, Clone)]
+pub struct LiftedConditionalEffect {
+    pub condition: Vec<LiftedAtom>,
+    pub negative_condition: Vec<LiftedAtom>,
+    pub add_effects: Vec<LiftedAtom>,
+    pub del_effects: Vec<LiftedAtom>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LiftedTask {
+    pub name: String,
+    pub parameters: Vec<Parameter>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LiftedSubtask {
+    pub id: String,
+    pub task: LiftedAtom,
+}
+
+/// A method; like for actions, `=` atoms in the precondition only constrain
+/// the instantiations.
+#[derive(Debug, Clone)]
+pub struct LiftedMethod {
+    pub name: String,
+    pub parameters: Vec<Parameter>,
+    pub task: LiftedAtom,
+    pub precondition: Vec<LiftedAtom>,
+    pub subtasks: Vec<LiftedSubtask>,
+    pub orderings: Vec<(String, String)>,
+}
+
+/// Types are given as `(type, parent type)` pairs; `object` is the implicit root.
+#[derive(Debug, Clone, Default)]
+pub struct LiftedDomain {
+    pub types: Vec<(String, String)>,
+    pub constants: Vec<(String, String)>,
+    pub tasks: Vec<LiftedTask>,
+    pub actions: Vec<LiftedAction>,
+    pub methods: Vec<LiftedMethod>,
+}
+
+/// Objects are `(name, type)` pairs; the initial facts, the goal and the
+/// subtasks of the initial network must be ground.
+#[derive(Debug, Clone, Default)]
+pub struct LiftedProblem {
+    pub objects: Vec<(String, String)>,
+    pub init: Vec<LiftedAtom>,
+    pub goal: Option<Vec<LiftedAtom>>,
+    pub subtasks: Vec<LiftedSubtask>,
+    pub orderings: Vec<(String, String)>,
+}
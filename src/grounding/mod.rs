@@ -0,0 +1,8 @@
+mod error;
+mod grounder;
+mod lifted;
+mod objects;
+
+pub use error::GroundingError;
+pub use grounder::{ground, ground_name, GroundProblem};
+pub use lifted::*;
@@ -0,0 +1,101 @@
+use std::collections::{HashMap, HashSet};
+
+use super::error::GroundingError;
+use super::lifted::{LiftedDomain, LiftedProblem};
+
+#[derive(Debug, Default)]
+pub struct TypedObjects {
+    pub list: Vec<String>,
+    pub set: HashSet<String>,
+}
+
+impl TypedObjects {
+    fn insert(&mut self, object: &str) {
+        if self.set.insert(object.to_string()) {
+            self.list.push(object.to_string());
+        }
+    }
+}
+
+/// The objects of every type, including those inherited from subtypes.
+#[derive(Debug)]
+pub struct Objects {
+    by_type: HashMap<String, TypedObjects>,
+}
+
+impl Objects {
+    pub fn new(domain: &LiftedDomain, problem: &LiftedProblem) -> Result<Objects, GroundingError> {
+        let mut parents: HashMap<&str, &str> = HashMap::new();
+        let mut by_type: HashMap<String, TypedObjects> = HashMap::new();
+        by_type.insert("object".to_string(), TypedObjects::default());
+        for (name, parent) in domain.types.iter() {
+            by_type.entry(name.clone()).or_default();
+            if name != "object" {
+                parents.insert(name, parent);
+            }
+        }
+        for parent in parents.values() {
+            if !by_type.contains_key(*parent) {
+                return Err(GroundingError::UndefinedType(parent.to_string()));
+            }
+        }
+        for (object, type_name) in domain.constants.iter().chain(problem.objects.iter()) {
+            let mut current = type_name.as_str();
+            let mut visited = HashSet::new();
+            loop {
+                by_type
+                    .get_mut(current)
+                    .ok_or_else(|| GroundingError::UndefinedType(current.to_string()))?
+                    .insert(object);
+                // `visited` guards against cyclic type declarations
+                match parents.get(current) {
+                    Some(parent) if visited.insert(current) => current = parent,
+                    _ => break,
+                }
+            }
+            by_type.get_mut("object").unwrap().insert(object);
+        }
+        Ok(Objects { by_type })
+    }
+
+    pub fn of_type(&self, type_name: &str) -> Result<&TypedObjects, GroundingError> {
+        self.by_type
+            .get(type_name)
+            .ok_or_else(|| GroundingError::UndefinedType(type_name.to_string()))
+    }
+
+    pub fn contains(&self, object: &str) -> bool {
+        self.by_type["object"].set.contains(object)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn type_hierarchy_test() {
+        let domain = LiftedDomain {
+            types: Vec::from([
+                ("locatable".to_string(), "object".to_string()),
+                ("vehicle".to_string(), "locatable".to_string()),
+                ("location".to_string(), "object".to_string()),
+            ]),
+            constants: Vec::from([("depot".to_string(), "location".to_string())]),
+            ..Default::default()
+        };
+        let problem = LiftedProblem {
+            objects: Vec::from([("truck".to_string(), "vehicle".to_string())]),
+            ..Default::default()
+        };
+        let objects = Objects::new(&domain, &problem).unwrap();
+        assert_eq!(objects.of_type("locatable").unwrap().list, vec!["truck"]);
+        assert_eq!(objects.of_type("location").unwrap().list, vec!["depot"]);
+        assert_eq!(objects.of_type("object").unwrap().list.len(), 2);
+        assert!(objects.contains("depot"));
+        assert_eq!(
+            objects.of_type("city").unwrap_err(),
+            GroundingError::UndefinedType("city".to_string())
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use super::error::Position;
+
+/// A (possibly typed) parameter, constant or object declaration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedName {
+    pub name: String,
+    pub type_name: String,
+    pub position: Position,
+}
+
+/// A predicate or task applied to arguments; arguments are either
+/// variables (`?x`) or constants.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Atom {
+    pub name: String,
+    pub args: Vec<String>,
+    pub position: Position,
+}
+
+/// A conjunction of literals.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Condition {
+    pub positive: Vec<Atom>,
+    pub negative: Vec<Atom>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Effect {
+    pub add: Vec<Atom>,
+    pub delete: Vec<Atom>,
+    pub conditional: Vec<ConditionalEffectDef>,
+}
+
+/// A `when` effect; its own effect is unconditional.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConditionalEffectDef {
+    pub condition: Condition,
+    pub effect: Effect,
+    pub position: Position,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PredicateDef {
+    pub name: String,
+    pub parameters: Vec<TypedName>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskDef {
+    pub name: String,
+    pub parameters: Vec<TypedName>,
+    pub position: Position,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActionDef {
+    pub name: String,
+    pub parameters: Vec<TypedName>,
+    pub precondition: Condition,
+    pub effect: Effect,
+    pub position: Position,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Subtask {
+    pub id: String,
+    pub task: Atom,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ordering {
+    pub before: String,
+    pub after: String,
+    pub position: Position,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaskNetworkDef {
+    pub parameters: Vec<TypedName>,
+    pub subtasks: Vec<Subtask>,
+    pub orderings: Vec<Ordering>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MethodDef {
+    pub name: String,
+    pub parameters: Vec<TypedName>,
+    pub task: Atom,
+    pub precondition: Condition,
+    pub network: TaskNetworkDef,
+    pub position: Position,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DomainDef {
+    pub name: String,
+    pub requirements: Vec<String>,
+    pub types: Vec<TypedName>,
+    pub constants: Vec<TypedName>,
+    pub predicates: Vec<PredicateDef>,
+    pub tasks: Vec<TaskDef>,
+    pub methods: Vec<MethodDef>,
+    pub actions: Vec<ActionDef>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProblemDef {
+    pub name: String,
+    pub domain: String,
+    pub objects: Vec<TypedName>,
+    pub network: TaskNetworkDef,
+    pub init: Vec<Atom>,
+    pub goal: Condition,
+    // Where the goal was declared, if any
+    pub goal_position: Option<Position>,
+}
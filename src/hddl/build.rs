@@ -0,0 +1,319 @@
+use std::collections::{HashMap, HashSet};
+
+use super::ast::*;
+use super::error::{HddlError, ParseError, Position};
+use crate::grounding::{self, GroundProblem, LiftedAction, LiftedAtom, LiftedDomain, LiftedMethod};
+use crate::grounding::{LiftedConditionalEffect, LiftedProblem, LiftedSubtask, LiftedTask, Parameter};
+
+/// Grounds a parsed domain and problem.
+pub fn build_problem(domain: &DomainDef, problem: &ProblemDef) -> Result<GroundProblem, HddlError> {
+    let lifted_domain = lift_domain(domain).map_err(HddlError::Domain)?;
+    let lifted_problem = lift_problem(domain, problem).map_err(HddlError::Problem)?;
+    grounding::ground(&lifted_domain, &lifted_problem).map_err(HddlError::Grounding)
+}
+
+// Task names with their arity, to report undefined references with a position
+fn signatures(domain: &DomainDef) -> Result<HashMap<&str, usize>, ParseError> {
+    let mut result = HashMap::new();
+    let declared = domain
+        .actions
+        .iter()
+        .map(|x| (&x.name, x.parameters.len(), x.position))
+        .chain(domain.tasks.iter().map(|x| (&x.name, x.parameters.len(), x.position)));
+    for (name, arity, position) in declared {
+        if result.insert(name.as_str(), arity).is_some() {
+            return Err(ParseError::new(position, format!("`{}` is declared twice", name)));
+        }
+    }
+    Ok(result)
+}
+
+fn check_task(signatures: &HashMap<&str, usize>, task: &Atom) -> Result<(), ParseError> {
+    match signatures.get(task.name.as_str()) {
+        None => Err(ParseError::new(task.position, format!("undefined task `{}`", task.name))),
+        Some(arity) if *arity != task.args.len() => Err(ParseError::new(
+            task.position,
+            format!("`{}` expects {} arguments, found {}", task.name, arity, task.args.len()),
+        )),
+        Some(_) => Ok(()),
+    }
+}
+
+fn lift_domain(domain: &DomainDef) -> Result<LiftedDomain, ParseError> {
+    let signatures = signatures(domain)?;
+    let mut actions = Vec::with_capacity(domain.actions.len());
+    for action in domain.actions.iter() {
+        actions.push(LiftedAction {
+            name: action.name.clone(),
+            parameters: parameters(&action.parameters),
+            precondition: atoms(&action.precondition.positive),
+            negative_precondition: atoms(&action.precondition.negative),
+            add_effects: atoms(&action.effect.add),
+            del_effects: atoms(&action.effect.delete),
+            conditional_effects: action
+                .effect
+                .conditional
+                .iter()
+                .map(|x| LiftedConditionalEffect {
+                    condition: atoms(&x.condition.positive),
+                    negative_condition: atoms(&x.condition.negative),
+                    add_effects: atoms(&x.effect.add),
+                    del_effects: atoms(&x.effect.delete),
+                })
+                .collect(),
+        });
+    }
+    let mut methods = Vec::with_capacity(domain.methods.len());
+    for method in domain.methods.iter() {
+        check_task(&signatures, &method.task)?;
+        if !domain.tasks.iter().any(|t| t.name == method.task.name) {
+            return Err(ParseError::new(
+                method.task.position,
+                format!("method `{}` decomposes the primitive task `{}`", method.name, method.task.name),
+            ));
+        }
+        if let Some(literal) = method.precondition.negative.first() {
+            return Err(ParseError::new(
+                literal.position,
+                "negative method preconditions are not supported",
+            ));
+        }
+        let (subtasks, orderings) = network(&signatures, &method.network)?;
+        methods.push(LiftedMethod {
+            name: method.name.clone(),
+            parameters: parameters(&method.parameters),
+            task: atom(&method.task),
+            precondition: atoms(&method.precondition.positive),
+            subtasks,
+            orderings,
+        });
+    }
+    Ok(LiftedDomain {
+        types: typed(&domain.types),
+        constants: typed(&domain.constants),
+        tasks: domain
+            .tasks
+            .iter()
+            .map(|t| LiftedTask {
+                name: t.name.clone(),
+                parameters: parameters(&t.parameters),
+            })
+            .collect(),
+        actions,
+        methods,
+    })
+}
+
+fn lift_problem(domain: &DomainDef, problem: &ProblemDef) -> Result<LiftedProblem, ParseError> {
+    if let Some(literal) = problem.goal.negative.first() {
+        return Err(ParseError::new(literal.position, "negative goals are not supported"));
+    }
+    if let Some(p) = problem.network.parameters.first() {
+        return Err(ParseError::new(
+            p.position,
+            "parameters of the initial task network are not supported",
+        ));
+    }
+    // Only task references are checked here, the domain itself is reported separately
+    let signatures = signatures(domain).unwrap_or_default();
+    let (subtasks, orderings) = network(&signatures, &problem.network)?;
+    Ok(LiftedProblem {
+        objects: typed(&problem.objects),
+        init: atoms(&problem.init),
+        goal: problem.goal_position.map(|_| atoms(&problem.goal.positive)),
+        subtasks,
+        orderings,
+    })
+}
+
+type LiftedNetwork = (Vec<LiftedSubtask>, Vec<(String, String)>);
+
+fn network(signatures: &HashMap<&str, usize>, network: &TaskNetworkDef) -> Result<LiftedNetwork, ParseError> {
+    let mut ids = HashSet::new();
+    let mut subtasks = Vec::with_capacity(network.subtasks.len());
+    for subtask in network.subtasks.iter() {
+        check_task(signatures, &subtask.task)?;
+        if !ids.insert(subtask.id.as_str()) {
+            return Err(ParseError::new(
+                subtask.task.position,
+                format!("duplicate subtask id `{}`", subtask.id),
+            ));
+        }
+        subtasks.push(LiftedSubtask {
+            id: subtask.id.clone(),
+            task: atom(&subtask.task),
+        });
+    }
+    let mut orderings = Vec::with_capacity(network.orderings.len());
+    for ordering in network.orderings.iter() {
+        for id in [&ordering.before, &ordering.after] {
+            if !ids.contains(id.as_str()) {
+                return Err(undefined_subtask(ordering.position, id));
+            }
+        }
+        orderings.push((ordering.before.clone(), ordering.after.clone()));
+    }
+    Ok((subtasks, orderings))
+}
+
+fn undefined_subtask(position: Position, id: &str) -> ParseError {
+    ParseError::new(position, format!("undefined subtask id `{}`", id))
+}
+
+fn atom(atom: &Atom) -> LiftedAtom {
+    LiftedAtom {
+        name: atom.name.clone(),
+        args: atom.args.clone(),
+    }
+}
+
+fn atoms(atoms: &[Atom]) -> Vec<LiftedAtom> {
+    atoms.iter().map(atom).collect()
+}
+
+fn parameters(parameters: &[TypedName]) -> Vec<Parameter> {
+    parameters
+        .iter()
+        .map(|p| Parameter::new(&p.name, &p.type_name))
+        .collect()
+}
+
+fn typed(names: &[TypedName]) -> Vec<(String, String)> {
+    names
+        .iter()
+        .map(|x| (x.name.clone(), x.type_name.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::hddl::reader::{parse_domain, parse_problem};
+    use crate::task_network::{Applicability, Task};
+
+    #[test]
+    fn recursive_method_test() {
+        let domain = parse_domain(
+            "(define (domain loop)
+               (:task repeat)
+               (:method m-again :task (repeat) :ordered-subtasks (and (step) (repeat)))
+               (:method m-done :task (repeat) :subtasks ())
+               (:action step))",
+        )
+        .unwrap();
+        let problem = parse_problem("(define (problem p) (:domain loop) (:htn :subtasks (repeat)))").unwrap();
+        let result = build_problem(&domain, &problem).unwrap();
+        assert!(result.initial_state.is_empty());
+        assert_eq!(result.initial_network.count_tasks(), 1);
+        if let Some(Task::Compound(c)) = result.initial_network.get_task(1) {
+            let methods = c.methods();
+            assert_eq!(methods.len(), 2);
+            let again = methods.iter().find(|m| m.name == "m-again").unwrap();
+            assert_eq!(again.decomposition.count_tasks(), 2);
+            assert_eq!(again.decomposition.get_unconstrained_tasks().len(), 1);
+        } else {
+            panic!("expected a compound task");
+        }
+        // The task refers to itself through m-again, yet is freed with the network
+        let repeat = Rc::downgrade(&result.tasks["repeat"]);
+        drop(result);
+        assert!(repeat.upgrade().is_none());
+    }
+
+    #[test]
+    fn undefined_task_test() {
+        let domain = parse_domain("(define (domain d) (:action a))").unwrap();
+        let problem = parse_problem("(define (problem p) (:domain d)\n (:htn :subtasks (and (b))))").unwrap();
+        match build_problem(&domain, &problem) {
+            Err(HddlError::Problem(e)) => assert_eq!((e.position.line, e.position.column), (2, 23)),
+            x => panic!("unexpected result {:?}", x),
+        }
+    }
+
+    #[test]
+    fn arity_test() {
+        let domain = parse_domain(
+            "(define (domain d)\n (:task t :parameters (?x))\n (:method m :parameters (?x) :task (t ?x ?x)))",
+        )
+        .unwrap();
+        let problem = parse_problem("(define (problem p) (:domain d))").unwrap();
+        match build_problem(&domain, &problem) {
+            Err(HddlError::Domain(e)) => assert_eq!(e.position, Position::new(3, 36)),
+            x => panic!("unexpected result {:?}", x),
+        }
+    }
+
+    #[test]
+    fn method_precondition_test() {
+        let domain = parse_domain(
+            "(define (domain d)
+               (:predicates (ready))
+               (:task t)
+               (:method m :task (t) :precondition (ready) :subtasks (a))
+               (:action a))",
+        )
+        .unwrap();
+        let problem = parse_problem("(define (problem p) (:domain d) (:htn :subtasks (t)) (:init (ready)))").unwrap();
+        let result = build_problem(&domain, &problem).unwrap();
+        if let Some(Task::Compound(c)) = result.initial_network.get_task(1) {
+            assert_eq!(c.methods()[0].preconditions, HashSet::from(["ready".to_string()]));
+        } else {
+            panic!("expected a compound task");
+        }
+    }
+
+    #[test]
+    fn negative_precondition_test() {
+        let domain = parse_domain(
+            "(define (domain d)
+               (:types place)
+               (:predicates (at ?p - place) (blocked ?p - place))
+               (:task go :parameters (?to - place))
+               (:method m :parameters (?from ?to - place) :task (go ?to) :subtasks (move ?from ?to))
+               (:action move :parameters (?from ?to - place)
+                 :precondition (and (at ?from) (not (= ?from ?to)) (not (blocked ?to)))
+                 :effect (and (not (at ?from)) (at ?to))))",
+        )
+        .unwrap();
+        let problem = parse_problem(
+            "(define (problem p) (:domain d) (:objects a b - place) (:htn :subtasks (go b)) (:init (at a)))",
+        )
+        .unwrap();
+        let result = build_problem(&domain, &problem).unwrap();
+        assert!(!result.tasks.contains_key("move a a"));
+        match result.tasks["move a b"].as_ref() {
+            Task::Primitive(a) => assert_eq!(a.negative_preconditions(), &HashSet::from(["blocked b".to_string()])),
+            _ => panic!("expected a primitive task"),
+        }
+    }
+
+    #[test]
+    fn conditional_effect_test() {
+        let domain = parse_domain(
+            "(define (domain d)
+               (:types door)
+               (:predicates (open ?d - door) (armed) (alarm ?d - door))
+               (:task enter)
+               (:method m :parameters (?d - door) :task (enter) :subtasks (open-door ?d))
+               (:action open-door :parameters (?d - door)
+                 :effect (and (open ?d) (when (and (armed) (not (open ?d))) (alarm ?d)))))",
+        )
+        .unwrap();
+        let problem = parse_problem(
+            "(define (problem p) (:domain d) (:objects front - door) (:htn :subtasks (enter)) (:init (armed)))",
+        )
+        .unwrap();
+        let result = build_problem(&domain, &problem).unwrap();
+        let Task::Primitive(action) = result.tasks["open-door front"].as_ref() else {
+            panic!("expected a primitive task");
+        };
+        let effect = &action.conditional_effects()[0];
+        assert_eq!(effect.condition, HashSet::from(["armed".to_string()]));
+        assert_eq!(effect.negative_condition, HashSet::from(["open front".to_string()]));
+        assert!(action.transition(&result.initial_state).contains("alarm front"));
+        let open = HashSet::from(["armed".to_string(), "open front".to_string()]);
+        assert!(!action.transition(&open).contains("alarm front"));
+    }
+}
@@ -0,0 +1,81 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use crate::grounding::GroundingError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    pub fn new(line: usize, column: usize) -> Position {
+        Position { line, column }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub position: Position,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(position: Position, message: impl Into<String>) -> ParseError {
+        ParseError {
+            position,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.position, self.message)
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Debug)]
+pub enum HddlError {
+    Io(io::Error),
+    Domain(ParseError),
+    Problem(ParseError),
+    Grounding(GroundingError),
+}
+
+impl fmt::Display for HddlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HddlError::Io(e) => write!(f, "{}", e),
+            HddlError::Domain(e) => write!(f, "domain {}", e),
+            HddlError::Problem(e) => write!(f, "problem {}", e),
+            HddlError::Grounding(e) => write!(f, "grounding failed: {}", e),
+        }
+    }
+}
+
+impl Error for HddlError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            HddlError::Io(e) => Some(e),
+            HddlError::Domain(e) | HddlError::Problem(e) => Some(e),
+            HddlError::Grounding(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for HddlError {
+    fn from(e: io::Error) -> HddlError {
+        HddlError::Io(e)
+    }
+}
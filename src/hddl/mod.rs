@@ -0,0 +1,200 @@
+mod ast;
+mod build;
+mod error;
+mod reader;
+mod sexpr;
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::domain::Problem;
+use crate::task_network::HTN;
+
+pub use ast::*;
+pub use crate::grounding::ground_name;
+pub use error::{HddlError, ParseError, Position};
+pub use reader::{parse_domain, parse_problem};
+
+/// Parses and grounds an HDDL domain and problem, including its goal, for
+/// `ProgressionSearch::solve`.
+pub fn load(domain: &str, problem: &str) -> Result<Problem<String>, HddlError> {
+    let domain = parse_domain(domain).map_err(HddlError::Domain)?;
+    let problem = parse_problem(problem).map_err(HddlError::Problem)?;
+    let ground = build::build_problem(&domain, &problem)?;
+    let result = Problem::new(ground.initial_state, ground.initial_network);
+    Ok(match ground.goal {
+        Some(goal) => result.with_goal(goal),
+        None => result,
+    })
+}
+
+/// Parses and grounds an HDDL domain and problem, returning the initial state
+/// and task network to hand to `ProgressionSearch::run`. Problems with a
+/// goal are rejected, as it would be lost; `load` keeps it.
+pub fn load_problem(domain: &str, problem: &str) -> Result<(HashSet<String>, HTN<String>), HddlError> {
+    let parsed = parse_problem(problem).map_err(HddlError::Problem)?;
+    if let Some(position) = parsed.goal_position {
+        if parsed.goal != Condition::default() {
+            return Err(HddlError::Problem(ParseError::new(position, "state goals need `load`")));
+        }
+    }
+    let result = load(domain, problem)?;
+    Ok((result.initial_state, result.initial_network))
+}
+
+pub fn read_problem_files(
+    domain: impl AsRef<Path>,
+    problem: impl AsRef<Path>,
+) -> Result<(HashSet<String>, HTN<String>), HddlError> {
+    let domain = fs::read_to_string(domain)?;
+    let problem = fs::read_to_string(problem)?;
+    load_problem(&domain, &problem)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::{ProgressionSearch, SearchResult};
+
+    const DOMAIN: &str = "
+        (define (domain construction)
+          (:requirements :hierarchy)
+          (:predicates (obtained_permit))
+          (:task construct)
+          (:method method-01
+            :task (construct)
+            :subtasks (and (t1 (BuildFoundation)) (t2 (BuildFrame)) (t3 (BuildRoof))
+                           (t4 (BuildWalls)) (t5 (BuildInterior)))
+            :ordering (and (< t1 t2) (< t2 t3) (< t2 t4) (< t3 t5) (< t4 t5)))
+          (:action ObtainPermit :effect (obtained_permit))
+          (:action HireBuilder :precondition (obtained_permit))
+          (:action PayBuilder)
+          (:action BuildFoundation)
+          (:action BuildFrame)
+          (:action BuildRoof)
+          (:action BuildWalls)
+          (:action BuildInterior))";
+
+    const PROBLEM: &str = "
+        (define (problem house) (:domain construction)
+          (:htn :ordered-subtasks (and (t1 (ObtainPermit)) (t2 (HireBuilder))
+                                       (t3 (construct)) (t4 (PayBuilder))))
+          (:init))";
+
+    #[test]
+    fn construction_domain_test() {
+        let (state, htn) = load_problem(DOMAIN, PROBLEM).unwrap();
+        assert!(state.is_empty());
+        assert_eq!(htn.count_tasks(), 4);
+        let mut search = ProgressionSearch::new();
+        match search.run(state, htn).unwrap() {
+            SearchResult::Solved { plan, .. } => {
+                let x = plan.action_names();
+                assert_eq!(x[0], "obtainpermit");
+                assert_eq!(x[1], "hirebuilder");
+                assert_eq!(x[2], "buildfoundation");
+                assert_eq!(x[7], "paybuilder");
+            }
+            x => panic!("expected a solution, got {:?}", x),
+        }
+    }
+
+    #[test]
+    fn lifted_domain_test() {
+        let domain = "
+            (define (domain transport)
+              (:types location vehicle package - object)
+              (:predicates (at ?x - object ?l - location) (in ?p - package ?v - vehicle)
+                           (road ?l1 ?l2 - location))
+              (:task deliver :parameters (?p - package ?l - location))
+              (:task get-to :parameters (?v - vehicle ?l - location))
+              (:method m-deliver
+                :parameters (?p - package ?l1 ?l2 - location ?v - vehicle)
+                :task (deliver ?p ?l2)
+                :ordered-subtasks (and (get-to ?v ?l1) (pick-up ?v ?l1 ?p)
+                                       (get-to ?v ?l2) (drop ?v ?l2 ?p)))
+              (:method m-drive-to
+                :parameters (?v - vehicle ?l1 ?l2 - location)
+                :task (get-to ?v ?l2)
+                :subtasks (drive ?v ?l1 ?l2))
+              (:method m-i-am-there
+                :parameters (?v - vehicle ?l - location)
+                :task (get-to ?v ?l)
+                :subtasks ())
+              (:action drive
+                :parameters (?v - vehicle ?l1 ?l2 - location)
+                :precondition (and (at ?v ?l1) (road ?l1 ?l2))
+                :effect (and (not (at ?v ?l1)) (at ?v ?l2)))
+              (:action pick-up
+                :parameters (?v - vehicle ?l - location ?p - package)
+                :precondition (and (at ?v ?l) (at ?p ?l))
+                :effect (and (not (at ?p ?l)) (in ?p ?v)))
+              (:action drop
+                :parameters (?v - vehicle ?l - location ?p - package)
+                :precondition (and (at ?v ?l) (in ?p ?v))
+                :effect (and (not (in ?p ?v)) (at ?p ?l))))";
+        let problem = "
+            (define (problem p1) (:domain transport)
+              (:objects city-a city-b - location truck - vehicle pkg - package)
+              (:htn :parameters () :subtasks (and (t1 (deliver pkg city-a))))
+              (:init (road city-a city-b) (road city-b city-a) (at truck city-a) (at pkg city-b)))";
+        let (state, htn) = load_problem(domain, problem).unwrap();
+        assert!(state.contains("road city-a city-b"));
+        let mut search = ProgressionSearch::new();
+        match search.run(state, htn).unwrap() {
+            SearchResult::Solved { plan, .. } => assert_eq!(
+                plan.action_names(),
+                vec!["drive truck city-a city-b", "pick-up truck city-b pkg", "drive truck city-b city-a", "drop truck city-a pkg"]
+            ),
+            x => panic!("expected a solution, got {:?}", x),
+        }
+    }
+
+    #[test]
+    fn goal_test() {
+        let problem = "
+            (define (problem house) (:domain construction)
+              (:htn :subtasks (and (t1 (ObtainPermit)) (t2 (PayBuilder))))
+              (:goal (and (obtained_permit)))
+              (:init))";
+        let result = load(DOMAIN, problem).unwrap();
+        assert_eq!(result.goal, Some(HashSet::from(["obtained_permit".to_string()])));
+        match ProgressionSearch::new().solve(result).unwrap() {
+            SearchResult::Solved { plan, .. } => assert_eq!(plan.len(), 2),
+            x => panic!("expected a solution, got {:?}", x),
+        }
+        match load_problem(DOMAIN, problem) {
+            Err(HddlError::Problem(e)) => assert_eq!(e.position, Position::new(4, 15)),
+            x => panic!("unexpected result {:?}", x),
+        }
+        assert_eq!(load(DOMAIN, PROBLEM).unwrap().goal, None);
+
+        // The goal cannot be reached without the permit
+        let problem = "
+            (define (problem house) (:domain construction)
+              (:htn :subtasks (t1 (PayBuilder)))
+              (:goal (obtained_permit))
+              (:init))";
+        assert!(matches!(ProgressionSearch::new().solve(load(DOMAIN, problem).unwrap()).unwrap(), SearchResult::Unsolvable));
+        let problem = "
+            (define (problem house) (:domain construction)
+              (:htn :subtasks (t1 (PayBuilder)))
+              (:goal (not (obtained_permit)))
+              (:init))";
+        match load(DOMAIN, problem) {
+            Err(HddlError::Problem(e)) => assert_eq!(e.position, Position::new(4, 27)),
+            x => panic!("unexpected result {:?}", x),
+        }
+    }
+
+    #[test]
+    fn error_reporting_test() {
+        match load_problem(DOMAIN, "(define (problem house)\n  (:domain construction) (:htn") {
+            Err(HddlError::Problem(e)) => assert_eq!(e.position, Position::new(2, 26)),
+            x => panic!("unexpected result {:?}", x),
+        }
+        let err = read_problem_files("/nonexistent/domain.hddl", "/nonexistent/problem.hddl");
+        assert!(matches!(err, Err(HddlError::Io(_))));
+    }
+}
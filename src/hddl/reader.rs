@@ -0,0 +1,476 @@
+use super::ast::*;
+use super::error::{ParseError, Position};
+use super::sexpr::{self, SExpr};
+
+pub fn parse_domain(src: &str) -> Result<DomainDef, ParseError> {
+    let expr = sexpr::read(src)?;
+    let items = define_body(&expr, "domain")?;
+    let mut domain = DomainDef {
+        name: items.0.to_string(),
+        ..Default::default()
+    };
+    for section in items.1 {
+        let list = section.expect_list("a domain section")?;
+        match section.head() {
+            Some(":requirements") => {
+                for r in &list[1..] {
+                    domain.requirements.push(r.expect_symbol("a requirement")?.to_string());
+                }
+            }
+            Some(":types") => domain.types.extend(typed_list(&list[1..])?),
+            Some(":constants") => domain.constants.extend(typed_list(&list[1..])?),
+            Some(":predicates") => {
+                for p in &list[1..] {
+                    let (name, params) = signature(p, "a predicate declaration")?;
+                    domain.predicates.push(PredicateDef {
+                        name,
+                        parameters: typed_list(params)?,
+                    });
+                }
+            }
+            Some(":task") => domain.tasks.push(task_def(section, list)?),
+            Some(":method") => domain.methods.push(method_def(section, list)?),
+            Some(":action") => domain.actions.push(action_def(section, list)?),
+            _ => return Err(unexpected(section, "domain section")),
+        }
+    }
+    Ok(domain)
+}
+
+pub fn parse_problem(src: &str) -> Result<ProblemDef, ParseError> {
+    let expr = sexpr::read(src)?;
+    let items = define_body(&expr, "problem")?;
+    let mut problem = ProblemDef {
+        name: items.0.to_string(),
+        ..Default::default()
+    };
+    for section in items.1 {
+        let list = section.expect_list("a problem section")?;
+        match section.head() {
+            Some(":domain") => {
+                let name = list
+                    .get(1)
+                    .ok_or_else(|| ParseError::new(section.position(), "missing domain name"))?;
+                problem.domain = name.expect_symbol("a domain name")?.to_string();
+            }
+            Some(":requirements") => {}
+            Some(":objects") => problem.objects.extend(typed_list(&list[1..])?),
+            Some(":htn") => problem.network = task_network(&keyword_args(&list[1..])?)?,
+            Some(":init") => {
+                for fact in &list[1..] {
+                    problem.init.push(atom(fact)?);
+                }
+            }
+            Some(":goal") => {
+                let goal = list
+                    .get(1)
+                    .ok_or_else(|| ParseError::new(section.position(), "missing goal condition"))?;
+                problem.goal = condition(goal)?;
+                problem.goal_position = Some(section.position());
+            }
+            _ => return Err(unexpected(section, "problem section")),
+        }
+    }
+    Ok(problem)
+}
+
+// Checks the `(define (<kind> <name>) ...)` wrapper, returning the name and sections
+fn define_body<'a>(expr: &'a SExpr, kind: &str) -> Result<(&'a str, &'a [SExpr]), ParseError> {
+    let items = expr.expect_list("`(define ...)`")?;
+    if expr.head() != Some("define") {
+        return Err(ParseError::new(expr.position(), "expected `(define ...)`"));
+    }
+    let header = items
+        .get(1)
+        .ok_or_else(|| ParseError::new(expr.position(), format!("missing `({} <name>)`", kind)))?;
+    let (head, rest) = signature(header, kind)?;
+    if head != kind || rest.len() != 1 {
+        return Err(ParseError::new(header.position(), format!("expected `({} <name>)`", kind)));
+    }
+    Ok((rest[0].expect_symbol("a name")?, &items[2..]))
+}
+
+fn unexpected(expr: &SExpr, what: &str) -> ParseError {
+    let found = expr.head().map(|x| x.to_string()).unwrap_or_else(|| expr.to_string());
+    ParseError::new(expr.position(), format!("unexpected {} `{}`", what, found))
+}
+
+// Splits `(name args...)` into the name and the remaining elements
+fn signature<'a>(expr: &'a SExpr, what: &str) -> Result<(String, &'a [SExpr]), ParseError> {
+    let items = expr.expect_list(what)?;
+    let name = items
+        .first()
+        .ok_or_else(|| ParseError::new(expr.position(), format!("empty {}", what)))?
+        .expect_symbol("a name")?;
+    Ok((name.to_string(), &items[1..]))
+}
+
+// Parses `a b - t1 c - t2 d`; untyped names default to `object`
+fn typed_list(items: &[SExpr]) -> Result<Vec<TypedName>, ParseError> {
+    let mut result = Vec::new();
+    let mut pending: Vec<(String, Position)> = Vec::new();
+    let mut iter = items.iter();
+    while let Some(item) = iter.next() {
+        let symbol = item.expect_symbol("a name")?;
+        if symbol == "-" {
+            let type_expr = iter
+                .next()
+                .ok_or_else(|| ParseError::new(item.position(), "missing type after `-`"))?;
+            let type_name = match type_expr {
+                SExpr::Symbol(s, _) => s.clone(),
+                SExpr::List(..) if type_expr.head() == Some("either") => {
+                    return Err(ParseError::new(type_expr.position(), "`either` types are not supported"))
+                }
+                _ => return Err(unexpected(type_expr, "type")),
+            };
+            if pending.is_empty() {
+                return Err(ParseError::new(item.position(), "type without preceding names"));
+            }
+            for (name, position) in pending.drain(..) {
+                result.push(TypedName {
+                    name,
+                    type_name: type_name.clone(),
+                    position,
+                });
+            }
+        } else {
+            pending.push((symbol.to_string(), item.position()));
+        }
+    }
+    for (name, position) in pending {
+        result.push(TypedName {
+            name,
+            type_name: "object".to_string(),
+            position,
+        });
+    }
+    Ok(result)
+}
+
+// Collects the `:keyword value` pairs following an operator's name
+fn keyword_args(items: &[SExpr]) -> Result<Vec<(&str, &SExpr)>, ParseError> {
+    let mut result = Vec::new();
+    let mut iter = items.iter();
+    while let Some(key) = iter.next() {
+        let name = key.expect_symbol("a keyword")?;
+        if !name.starts_with(':') {
+            return Err(ParseError::new(key.position(), format!("expected a keyword, found `{}`", name)));
+        }
+        let value = iter
+            .next()
+            .ok_or_else(|| ParseError::new(key.position(), format!("missing value for `{}`", name)))?;
+        result.push((name, value));
+    }
+    Ok(result)
+}
+
+fn operator_name(expr: &SExpr, list: &[SExpr]) -> Result<String, ParseError> {
+    let name = list
+        .get(1)
+        .ok_or_else(|| ParseError::new(expr.position(), "missing name"))?;
+    Ok(name.expect_symbol("a name")?.to_string())
+}
+
+fn parameters(value: &SExpr) -> Result<Vec<TypedName>, ParseError> {
+    typed_list(value.expect_list("a parameter list")?)
+}
+
+fn task_def(expr: &SExpr, list: &[SExpr]) -> Result<TaskDef, ParseError> {
+    let name = operator_name(expr, list)?;
+    let mut params = Vec::new();
+    for (key, value) in keyword_args(&list[2..])? {
+        match key {
+            ":parameters" => params = parameters(value)?,
+            _ => return Err(ParseError::new(value.position(), format!("unexpected `{}` in task", key))),
+        }
+    }
+    Ok(TaskDef {
+        name,
+        parameters: params,
+        position: expr.position(),
+    })
+}
+
+fn action_def(expr: &SExpr, list: &[SExpr]) -> Result<ActionDef, ParseError> {
+    let mut action = ActionDef {
+        name: operator_name(expr, list)?,
+        parameters: Vec::new(),
+        precondition: Condition::default(),
+        effect: Effect::default(),
+        position: expr.position(),
+    };
+    for (key, value) in keyword_args(&list[2..])? {
+        match key {
+            ":parameters" => action.parameters = parameters(value)?,
+            ":precondition" => action.precondition = condition(value)?,
+            ":effect" => action.effect = effect(value)?,
+            _ => return Err(ParseError::new(value.position(), format!("unexpected `{}` in action", key))),
+        }
+    }
+    Ok(action)
+}
+
+fn method_def(expr: &SExpr, list: &[SExpr]) -> Result<MethodDef, ParseError> {
+    let name = operator_name(expr, list)?;
+    let args = keyword_args(&list[2..])?;
+    let mut params = Vec::new();
+    let mut task = None;
+    let mut precondition = Condition::default();
+    for (key, value) in args.iter() {
+        match *key {
+            ":parameters" => params = parameters(value)?,
+            ":task" => task = Some(atom(value)?),
+            ":precondition" => precondition = condition(value)?,
+            _ => {}
+        }
+    }
+    let network_args: Vec<(&str, &SExpr)> = args
+        .into_iter()
+        .filter(|(k, _)| !matches!(*k, ":parameters" | ":task" | ":precondition"))
+        .collect();
+    Ok(MethodDef {
+        name,
+        parameters: params,
+        task: task.ok_or_else(|| ParseError::new(expr.position(), "method without `:task`"))?,
+        precondition,
+        network: task_network(&network_args)?,
+        position: expr.position(),
+    })
+}
+
+fn task_network(args: &[(&str, &SExpr)]) -> Result<TaskNetworkDef, ParseError> {
+    let mut network = TaskNetworkDef::default();
+    let mut ordered = false;
+    for (key, value) in args {
+        match *key {
+            ":parameters" => network.parameters = parameters(value)?,
+            ":subtasks" | ":tasks" => network.subtasks = subtasks(value)?,
+            ":ordered-subtasks" | ":ordered-tasks" => {
+                network.subtasks = subtasks(value)?;
+                ordered = true;
+            }
+            ":ordering" | ":order" => network.orderings = orderings(value)?,
+            ":constraints" => {
+                if !is_empty_conjunction(value) {
+                    return Err(ParseError::new(value.position(), "constraints are not supported"));
+                }
+            }
+            _ => return Err(ParseError::new(value.position(), format!("unexpected `{}` in task network", key))),
+        }
+    }
+    if ordered {
+        for pair in network.subtasks.windows(2) {
+            network.orderings.push(Ordering {
+                before: pair[0].id.clone(),
+                after: pair[1].id.clone(),
+                position: pair[1].task.position,
+            });
+        }
+    }
+    Ok(network)
+}
+
+fn is_empty_conjunction(expr: &SExpr) -> bool {
+    match expr.as_list() {
+        Some(items) => items.is_empty() || (items.len() == 1 && expr.head() == Some("and")),
+        None => false,
+    }
+}
+
+// Flattens `(and x y ...)` into its conjuncts; `()` is the empty conjunction
+fn conjuncts(expr: &SExpr) -> Result<Vec<&SExpr>, ParseError> {
+    let items = expr.expect_list("a list")?;
+    if items.is_empty() {
+        return Ok(Vec::new());
+    }
+    if expr.head() == Some("and") {
+        let mut result = Vec::new();
+        for item in &items[1..] {
+            result.extend(conjuncts(item)?);
+        }
+        Ok(result)
+    } else {
+        Ok(vec![expr])
+    }
+}
+
+fn subtasks(expr: &SExpr) -> Result<Vec<Subtask>, ParseError> {
+    let mut result = Vec::new();
+    for (i, entry) in conjuncts(expr)?.into_iter().enumerate() {
+        let items = entry.expect_list("a subtask")?;
+        // `(id (task args))` or just `(task args)`
+        if items.len() == 2 && items[1].as_list().is_some() {
+            result.push(Subtask {
+                id: items[0].expect_symbol("a subtask id")?.to_string(),
+                task: atom(&items[1])?,
+            });
+        } else {
+            result.push(Subtask {
+                id: format!("__t{}", i),
+                task: atom(entry)?,
+            });
+        }
+    }
+    Ok(result)
+}
+
+fn orderings(expr: &SExpr) -> Result<Vec<Ordering>, ParseError> {
+    let mut result = Vec::new();
+    for entry in conjuncts(expr)? {
+        let items = entry.expect_list("an ordering constraint")?;
+        let symbols: Vec<&str> = items
+            .iter()
+            .map(|x| x.expect_symbol("a subtask id"))
+            .collect::<Result<_, _>>()?;
+        match symbols.as_slice() {
+            ["<", a, b] | [">", b, a] => result.push(Ordering {
+                before: a.to_string(),
+                after: b.to_string(),
+                position: entry.position(),
+            }),
+            _ => return Err(ParseError::new(entry.position(), format!("invalid ordering `{}`", entry))),
+        }
+    }
+    Ok(result)
+}
+
+fn atom(expr: &SExpr) -> Result<Atom, ParseError> {
+    let (name, args) = signature(expr, "an atom")?;
+    let args = args
+        .iter()
+        .map(|x| x.expect_symbol("an argument").map(|s| s.to_string()))
+        .collect::<Result<_, _>>()?;
+    Ok(Atom {
+        name,
+        args,
+        position: expr.position(),
+    })
+}
+
+fn condition(expr: &SExpr) -> Result<Condition, ParseError> {
+    let mut result = Condition::default();
+    for literal in conjuncts(expr)? {
+        match literal.head() {
+            Some("not") => result.negative.push(negated_atom(literal)?),
+            Some("or" | "imply" | "forall" | "exists" | "when") => {
+                return Err(unsupported(literal));
+            }
+            _ => result.positive.push(atom(literal)?),
+        }
+    }
+    Ok(result)
+}
+
+fn effect(expr: &SExpr) -> Result<Effect, ParseError> {
+    let mut result = Effect::default();
+    for literal in conjuncts(expr)? {
+        match literal.head() {
+            Some("not") => result.delete.push(negated_atom(literal)?),
+            Some("when") => result.conditional.push(conditional_effect(literal)?),
+            Some("forall" | "increase") => return Err(unsupported(literal)),
+            _ => result.add.push(atom(literal)?),
+        }
+    }
+    Ok(result)
+}
+
+fn conditional_effect(expr: &SExpr) -> Result<ConditionalEffectDef, ParseError> {
+    let Some([_, condition_expr, effect_expr]) = expr.as_list() else {
+        return Err(ParseError::new(expr.position(), "`when` expects a condition and an effect"));
+    };
+    let effect = effect(effect_expr)?;
+    if let Some(nested) = effect.conditional.first() {
+        return Err(ParseError::new(nested.position, "nested `when` effects are not supported"));
+    }
+    Ok(ConditionalEffectDef {
+        condition: condition(condition_expr)?,
+        effect,
+        position: expr.position(),
+    })
+}
+
+fn negated_atom(expr: &SExpr) -> Result<Atom, ParseError> {
+    match expr.as_list() {
+        Some([_, inner]) => atom(inner),
+        _ => Err(ParseError::new(expr.position(), "`not` expects exactly one atom")),
+    }
+}
+
+fn unsupported(expr: &SExpr) -> ParseError {
+    ParseError::new(
+        expr.position(),
+        format!("`{}` expressions are not supported", expr.head().unwrap_or_default()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOMAIN: &str = "
+        (define (domain transport)
+          (:requirements :negative-preconditions :hierarchy :typing)
+          (:types location target locatable - object
+                  vehicle package - locatable)
+          (:predicates (at ?x - locatable ?v - location) (road ?l1 ?l2 - location))
+          (:task deliver :parameters (?p - package ?l - location))
+          (:method m-deliver
+            :parameters (?p - package ?l1 ?l2 - location ?v - vehicle)
+            :task (deliver ?p ?l2)
+            :ordered-subtasks (and (t1 (pick-up ?v ?l1 ?p)) (t2 (drop ?v ?l2 ?p))))
+          (:action drive
+            :parameters (?v - vehicle ?l1 ?l2 - location)
+            :precondition (and (at ?v ?l1) (road ?l1 ?l2) (not (at ?v ?l2)))
+            :effect (and (not (at ?v ?l1)) (at ?v ?l2))))";
+
+    #[test]
+    fn parse_domain_test() {
+        let domain = parse_domain(DOMAIN).unwrap();
+        assert_eq!(domain.name, "transport");
+        assert_eq!(domain.types.len(), 5);
+        assert_eq!(domain.types[3].name, "vehicle");
+        assert_eq!(domain.types[3].type_name, "locatable");
+        assert_eq!(domain.predicates[1].parameters.len(), 2);
+        assert_eq!(domain.tasks[0].parameters[1].type_name, "location");
+
+        let method = &domain.methods[0];
+        assert_eq!(method.task.args, vec!["?p", "?l2"]);
+        assert_eq!(method.network.subtasks[1].id, "t2");
+        assert_eq!(method.network.subtasks[1].task.name, "drop");
+        assert_eq!(method.network.orderings.len(), 1);
+        assert_eq!(method.network.orderings[0].before, "t1");
+
+        let action = &domain.actions[0];
+        assert_eq!(action.precondition.positive.len(), 2);
+        assert_eq!(action.precondition.negative[0].args, vec!["?v", "?l2"]);
+        assert_eq!(action.effect.add[0].name, "at");
+        assert_eq!(action.effect.delete.len(), 1);
+    }
+
+    #[test]
+    fn parse_problem_test() {
+        let problem = parse_problem(
+            "(define (problem p1) (:domain transport)
+               (:objects city-1 city-2 - location truck-0 - vehicle)
+               (:htn :parameters () :subtasks (and (task0 (deliver package-0 city-1)))
+                     :ordering ())
+               (:init (road city-1 city-2) (at truck-0 city-1)))",
+        )
+        .unwrap();
+        assert_eq!(problem.domain, "transport");
+        assert_eq!(problem.objects[2].type_name, "vehicle");
+        assert_eq!(problem.network.subtasks[0].id, "task0");
+        assert_eq!(problem.init.len(), 2);
+        assert_eq!(problem.goal_position, None);
+    }
+
+    #[test]
+    fn error_position_test() {
+        let err = parse_domain("(define (domain d)\n  (:action a\n    :effect (when (p) (when (q) (r)))))").unwrap_err();
+        assert_eq!(err.position, Position::new(3, 23));
+        let err = parse_domain("(define (domain d)\n  (:funny))").unwrap_err();
+        assert_eq!(err.position, Position::new(2, 3));
+        let err = parse_problem("(define (domain d))").unwrap_err();
+        assert_eq!(err.position, Position::new(1, 9));
+    }
+}
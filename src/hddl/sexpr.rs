@@ -0,0 +1,173 @@
+use super::error::{ParseError, Position};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SExpr {
+    Symbol(String, Position),
+    List(Vec<SExpr>, Position),
+}
+
+impl SExpr {
+    pub fn position(&self) -> Position {
+        match self {
+            SExpr::Symbol(_, pos) | SExpr::List(_, pos) => *pos,
+        }
+    }
+
+    pub fn as_symbol(&self) -> Option<&str> {
+        match self {
+            SExpr::Symbol(s, _) => Some(s),
+            SExpr::List(..) => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[SExpr]> {
+        match self {
+            SExpr::List(items, _) => Some(items),
+            SExpr::Symbol(..) => None,
+        }
+    }
+
+    pub fn expect_symbol(&self, what: &str) -> Result<&str, ParseError> {
+        self.as_symbol()
+            .ok_or_else(|| ParseError::new(self.position(), format!("expected {}, found a list", what)))
+    }
+
+    pub fn expect_list(&self, what: &str) -> Result<&[SExpr], ParseError> {
+        self.as_list().ok_or_else(|| {
+            ParseError::new(self.position(), format!("expected {}, found `{}`", what, self))
+        })
+    }
+
+    // Returns the head symbol of a list such as `(and ...)` or `(:action ...)`
+    pub fn head(&self) -> Option<&str> {
+        self.as_list().and_then(|items| items.first()).and_then(|x| x.as_symbol())
+    }
+}
+
+impl std::fmt::Display for SExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SExpr::Symbol(s, _) => write!(f, "{}", s),
+            SExpr::List(items, _) => {
+                write!(f, "(")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Symbol(String),
+}
+
+// HDDL is case-insensitive, so all symbols are lower-cased while lexing.
+fn tokenize(src: &str) -> Vec<(Token, Position)> {
+    let mut tokens = Vec::new();
+    let mut chars = src.chars().peekable();
+    let (mut line, mut column) = (1, 1);
+    while let Some(&c) = chars.peek() {
+        let pos = Position::new(line, column);
+        if c == '\n' {
+            chars.next();
+            line += 1;
+            column = 1;
+        } else if c.is_whitespace() {
+            chars.next();
+            column += 1;
+        } else if c == ';' {
+            while let Some(&c) = chars.peek() {
+                if c == '\n' {
+                    break;
+                }
+                chars.next();
+                column += 1;
+            }
+        } else if c == '(' || c == ')' {
+            chars.next();
+            column += 1;
+            tokens.push((if c == '(' { Token::Open } else { Token::Close }, pos));
+        } else {
+            let mut symbol = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || c == '(' || c == ')' || c == ';' {
+                    break;
+                }
+                symbol.extend(c.to_lowercase());
+                chars.next();
+                column += 1;
+            }
+            tokens.push((Token::Symbol(symbol), pos));
+        }
+    }
+    tokens
+}
+
+/// Reads a single top-level s-expression, rejecting anything but whitespace
+/// and comments after it.
+pub fn read(src: &str) -> Result<SExpr, ParseError> {
+    let tokens = tokenize(src);
+    let mut stack: Vec<(Vec<SExpr>, Position)> = Vec::new();
+    let mut result = None;
+    for (token, pos) in tokens {
+        if result.is_some() {
+            return Err(ParseError::new(pos, "unexpected input after the end of the definition"));
+        }
+        match token {
+            Token::Open => stack.push((Vec::new(), pos)),
+            Token::Close => {
+                let (items, start) = stack
+                    .pop()
+                    .ok_or_else(|| ParseError::new(pos, "unmatched `)`"))?;
+                let list = SExpr::List(items, start);
+                match stack.last_mut() {
+                    Some((parent, _)) => parent.push(list),
+                    None => result = Some(list),
+                }
+            }
+            Token::Symbol(s) => match stack.last_mut() {
+                Some((parent, _)) => parent.push(SExpr::Symbol(s, pos)),
+                None => return Err(ParseError::new(pos, format!("expected `(`, found `{}`", s))),
+            },
+        }
+    }
+    if let Some((_, start)) = stack.pop() {
+        return Err(ParseError::new(start, "unclosed `(`"));
+    }
+    result.ok_or_else(|| ParseError::new(Position::new(1, 1), "empty input"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_nested_test() {
+        let expr = read("(define ; comment\n  (Domain x) (:types a - b))").unwrap();
+        let items = expr.as_list().unwrap();
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].as_symbol(), Some("define"));
+        assert_eq!(items[1].head(), Some("domain"));
+        assert_eq!(items[1].position(), Position::new(2, 3));
+        assert_eq!(items[2].to_string(), "(:types a - b)");
+    }
+
+    #[test]
+    fn read_errors_test() {
+        let err = read("(define\n  (domain x)").unwrap_err();
+        assert_eq!(err.position, Position::new(1, 1));
+        let err = read("(a))").unwrap_err();
+        assert_eq!(err.position, Position::new(1, 4));
+        let err = read("(a) (b)").unwrap_err();
+        assert_eq!(err.position, Position::new(1, 5));
+        assert!(read("  ; nothing\n").is_err());
+    }
+}
@@ -0,0 +1,5 @@
+mod relaxed_composition;
+mod tdg;
+
+pub use relaxed_composition::{RelaxedComposition, RelaxedEstimate};
+pub use tdg::TdgHeuristic;
@@ -0,0 +1,314 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::hash::Hash;
+
+use crate::domain::Domain;
+use crate::search::{Heuristic, SearchNode};
+use crate::task_network::{Task, HTN};
+
+/// The classical delete-relaxation estimate computed on the compiled problem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelaxedEstimate {
+    Add,
+    Max,
+    FF,
+}
+
+// A relaxed operator, either a primitive action or a method with their
+// costs, which achieves the fact of the task it refines
+#[derive(Debug)]
+struct Operator {
+    preconditions: Vec<usize>,
+    add_effects: Vec<usize>,
+    cost: u32,
+}
+
+/// Relaxed composition heuristic: the hierarchy is compiled into a
+/// delete-relaxed classical problem in which every task has a fact that is
+/// reached once the task has been done. Actions add their effects and their
+/// task fact, methods add the fact of their task once all subtasks are done
+/// and their preconditions hold, and the goal is to do every task remaining
+/// in the node's network. Negative preconditions are relaxed away along with
+/// the delete effects, and conditional effects become free operators that
+/// need their condition and the fact of their action.
+#[derive(Debug)]
+pub struct RelaxedComposition<T: Hash + Eq> {
+    estimate: RelaxedEstimate,
+    facts: HashMap<T, usize>,
+    tasks: HashMap<String, usize>,
+    operators: Vec<Operator>,
+    // Operators each fact is a precondition of
+    consumers: Vec<Vec<usize>>,
+}
+
+impl<T: Hash + Eq + Clone> RelaxedComposition<T> {
+    pub fn new(initial_network: &HTN<T>, estimate: RelaxedEstimate) -> RelaxedComposition<T> {
+        RelaxedComposition::from_domain(&Domain::from_network(initial_network), estimate)
+    }
+
+    /// Compiles the domain once for every problem of it.
+    pub fn from_domain(domain: &Domain<T>, estimate: RelaxedEstimate) -> RelaxedComposition<T> {
+        // Task facts take the ids of their tasks in the domain
+        let tasks: HashMap<String, usize> = domain.tasks().map(|(id, t)| (t.get_name(), id)).collect();
+        let mut facts = HashMap::new();
+        let mut operators = Vec::new();
+        for (id, task) in domain.tasks() {
+            let mut fact_id = |fact: &T| {
+                let next = tasks.len() + facts.len();
+                *facts.entry(fact.clone()).or_insert(next)
+            };
+            match task.as_ref() {
+                Task::Primitive(action) => {
+                    let preconditions: Vec<usize> = action.preconditions().iter().map(&mut fact_id).collect();
+                    // Every conditional effect is an operator of its own, which
+                    // the action has already paid for
+                    for effect in action.conditional_effects() {
+                        let mut conditions: Vec<usize> = effect.condition.iter().map(&mut fact_id).collect();
+                        conditions.push(id);
+                        conditions.sort_unstable();
+                        conditions.dedup();
+                        operators.push(Operator {
+                            preconditions: conditions,
+                            add_effects: effect.add_effects.iter().map(&mut fact_id).collect(),
+                            cost: 0,
+                        });
+                    }
+                    let mut add_effects: Vec<usize> = action.add_effects().iter().map(&mut fact_id).collect();
+                    add_effects.push(id);
+                    operators.push(Operator {
+                        preconditions,
+                        add_effects,
+                        cost: action.cost(),
+                    });
+                }
+                Task::Compound(c) => {
+                    for method in c.methods().iter() {
+                        let network = &method.decomposition;
+                        let mut preconditions: HashSet<usize> = network
+                            .get_task_ids()
+                            .into_iter()
+                            .filter_map(|i| network.get_task(i))
+                            .map(|task| tasks[&task.get_name()])
+                            .collect();
+                        preconditions.extend(method.preconditions.iter().map(&mut fact_id));
+                        operators.push(Operator {
+                            preconditions: preconditions.into_iter().collect(),
+                            add_effects: vec![id],
+                            cost: method.cost,
+                        });
+                    }
+                }
+            }
+        }
+        let mut consumers = vec![Vec::new(); tasks.len() + facts.len()];
+        for (i, operator) in operators.iter().enumerate() {
+            for fact in operator.preconditions.iter() {
+                consumers[*fact].push(i);
+            }
+        }
+        RelaxedComposition {
+            estimate,
+            facts,
+            tasks,
+            operators,
+            consumers,
+        }
+    }
+
+    // Generalized Dijkstra over the relaxed problem, returning the cost of
+    // every fact and the operator that first achieved it at that cost
+    fn explore(&self, state: &HashSet<T>) -> (Vec<Option<u32>>, Vec<Option<usize>>) {
+        let mut costs = vec![None; self.consumers.len()];
+        let mut supporters = vec![None; self.consumers.len()];
+        let mut unsatisfied: Vec<usize> = self.operators.iter().map(|o| o.preconditions.len()).collect();
+        let mut queue = BinaryHeap::new();
+        for fact in state.iter().filter_map(|f| self.facts.get(f)) {
+            costs[*fact] = Some(0);
+            queue.push(Reverse((0, *fact)));
+        }
+        for (i, operator) in self.operators.iter().enumerate() {
+            if operator.preconditions.is_empty() {
+                self.achieve(i, operator.cost, &mut costs, &mut supporters, &mut queue);
+            }
+        }
+        let mut closed = vec![false; self.consumers.len()];
+        while let Some(Reverse((cost, fact))) = queue.pop() {
+            if closed[fact] || costs[fact] != Some(cost) {
+                continue;
+            }
+            closed[fact] = true;
+            for i in self.consumers[fact].iter() {
+                unsatisfied[*i] -= 1;
+                if unsatisfied[*i] == 0 {
+                    let operator = &self.operators[*i];
+                    let pre = operator.preconditions.iter().map(|p| costs[*p].unwrap());
+                    let cost = match self.estimate {
+                        RelaxedEstimate::Max => pre.max().unwrap_or(0),
+                        RelaxedEstimate::Add | RelaxedEstimate::FF => pre.fold(0, u32::saturating_add),
+                    };
+                    self.achieve(*i, cost.saturating_add(operator.cost), &mut costs, &mut supporters, &mut queue);
+                }
+            }
+        }
+        (costs, supporters)
+    }
+
+    fn achieve(
+        &self,
+        operator: usize,
+        cost: u32,
+        costs: &mut [Option<u32>],
+        supporters: &mut [Option<usize>],
+        queue: &mut BinaryHeap<Reverse<(u32, usize)>>,
+    ) {
+        for fact in self.operators[operator].add_effects.iter() {
+            if costs[*fact].is_none_or(|c| cost < c) {
+                costs[*fact] = Some(cost);
+                supporters[*fact] = Some(operator);
+                queue.push(Reverse((cost, *fact)));
+            }
+        }
+    }
+
+    // Cost of the relaxed plan extracted from the best supporters
+    fn relaxed_plan(&self, goals: &[usize], supporters: &[Option<usize>]) -> u32 {
+        let mut selected = HashSet::new();
+        let mut marked = HashSet::new();
+        let mut open: Vec<usize> = goals.to_vec();
+        while let Some(fact) = open.pop() {
+            if !marked.insert(fact) {
+                continue;
+            }
+            if let Some(operator) = supporters[fact] {
+                if selected.insert(operator) {
+                    open.extend(self.operators[operator].preconditions.iter());
+                }
+            }
+        }
+        selected.iter().map(|i| self.operators[*i].cost).sum()
+    }
+}
+
+impl<T: Hash + Eq + Clone> Heuristic<T> for RelaxedComposition<T> {
+    fn estimate(&self, node: &SearchNode<T>) -> Option<u32> {
+        let network = &node.network;
+        let goals: HashSet<usize> = network
+            .get_task_ids()
+            .into_iter()
+            .filter_map(|id| network.get_task(id))
+            .filter_map(|task| self.tasks.get(&task.get_name()).cloned())
+            .collect();
+        if goals.is_empty() {
+            return Some(0);
+        }
+        let goals: Vec<usize> = goals.into_iter().collect();
+        let (costs, supporters) = self.explore(&node.state);
+        let mut total: u32 = 0;
+        for goal in goals.iter() {
+            let cost = costs[*goal]?;
+            total = match self.estimate {
+                RelaxedEstimate::Max => total.max(cost),
+                _ => total.saturating_add(cost),
+            };
+        }
+        match self.estimate {
+            RelaxedEstimate::FF => Some(self.relaxed_plan(&goals, &supporters)),
+            _ => Some(total),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hddl::load_problem;
+    use crate::search::{ProgressionSearch, SearchResult, SearchStrategy, TieBreaking};
+
+    const DOMAIN: &str = "
+        (define (domain keys)
+          (:predicates (have-key) (open) (inside))
+          (:task enter)
+          (:method m-unlock :task (enter) :ordered-subtasks (and (get-key) (unlock) (go-in)))
+          (:method m-walk :task (enter) :ordered-subtasks (go-in))
+          (:action get-key :effect (have-key))
+          (:action unlock :precondition (have-key) :effect (open))
+          (:action go-in :precondition (open) :effect (inside)))";
+
+    fn problem(init: &str) -> String {
+        format!("(define (problem p) (:domain keys) (:htn :subtasks (enter)) (:init {}))", init)
+    }
+
+    #[test]
+    fn estimates_test() {
+        let (state, htn) = load_problem(DOMAIN, &problem("")).unwrap();
+        let node = SearchNode::new(state, htn.clone());
+        // go-in needs open from unlock, which needs have-key from get-key, so
+        // m-walk is the cheapest way to do enter under every estimate
+        let add = RelaxedComposition::new(&htn, RelaxedEstimate::Add);
+        let max = RelaxedComposition::new(&htn, RelaxedEstimate::Max);
+        let ff = RelaxedComposition::new(&htn, RelaxedEstimate::FF);
+        assert_eq!(add.estimate(&node), Some(3));
+        assert_eq!(max.estimate(&node), Some(3));
+        assert_eq!(ff.estimate(&node), Some(3));
+
+        // get-key is counted twice by hadd but shared in the relaxed plan
+        let two = "(define (problem p) (:domain keys) (:htn :subtasks (and (enter) (get-key))))";
+        let (state, htn) = load_problem(DOMAIN, two).unwrap();
+        let node = SearchNode::new(state, htn);
+        assert_eq!(add.estimate(&node), Some(4));
+        assert_eq!(max.estimate(&node), Some(3));
+        assert_eq!(ff.estimate(&node), Some(3));
+
+        let (state, htn) = load_problem(DOMAIN, &problem("(open)")).unwrap();
+        let node = SearchNode::new(state, htn);
+        assert_eq!(add.estimate(&node), Some(1));
+        assert_eq!(max.estimate(&node), Some(1));
+        assert_eq!(ff.estimate(&node), Some(1));
+    }
+
+    #[test]
+    fn conditional_effect_test() {
+        let domain = "
+            (define (domain d)
+              (:predicates (armed) (open) (inside))
+              (:task enter)
+              (:method m :task (enter) :ordered-subtasks (and (press) (go-in)))
+              (:action press :precondition (armed) :effect (when (armed) (open)))
+              (:action go-in :precondition (open) :effect (inside)))";
+        let problem = "(define (problem p) (:domain d) (:htn :subtasks (enter)) (:init (armed)))";
+        let (state, htn) = load_problem(domain, problem).unwrap();
+        let node = SearchNode::new(state.clone(), htn.clone());
+        // Opening the door comes with pressing, not on top of it
+        let ff = RelaxedComposition::new(&htn, RelaxedEstimate::FF);
+        assert_eq!(ff.estimate(&node), Some(2));
+        let max = RelaxedComposition::new(&htn, RelaxedEstimate::Max);
+        assert_eq!(max.estimate(&node), Some(2));
+    }
+
+    #[test]
+    fn dead_end_test() {
+        let domain = "
+            (define (domain d)
+              (:predicates (p))
+              (:task t)
+              (:method m :task (t) :subtasks (a))
+              (:action a :precondition (p)))";
+        let problem = "(define (problem p) (:domain d) (:htn :subtasks (t)))";
+        let (state, htn) = load_problem(domain, problem).unwrap();
+        let h = RelaxedComposition::new(&htn, RelaxedEstimate::FF);
+        assert_eq!(h.estimate(&SearchNode::new(state, htn)), None);
+    }
+
+    #[test]
+    fn search_test() {
+        let (state, htn) = load_problem(DOMAIN, &problem("")).unwrap();
+        for estimate in [RelaxedEstimate::Add, RelaxedEstimate::Max, RelaxedEstimate::FF] {
+            let h = Box::new(RelaxedComposition::new(&htn, estimate));
+            let mut search = ProgressionSearch::with_strategy(SearchStrategy::GreedyBestFirst(h), TieBreaking::Fifo);
+            match search.run(state.clone(), htn.clone()).unwrap() {
+                SearchResult::Solved { plan, .. } => assert_eq!(plan.action_names(), vec!["get-key", "unlock", "go-in"]),
+                x => panic!("expected a solution, got {:?}", x),
+            }
+        }
+    }
+}
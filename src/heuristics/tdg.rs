@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::rc::Rc;
+
+use crate::domain::Domain;
+use crate::search::{Heuristic, SearchNode};
+use crate::task_network::{Task, HTN};
+
+/// Estimates the cost still to be paid from the task decomposition graph:
+/// every primitive task costs as much as its action, and every compound task
+/// at least as much as its cheapest method and that method's subtasks. The
+/// estimate is admissible, and nodes containing tasks without any refinement
+/// are reported as dead ends.
+#[derive(Debug)]
+pub struct TdgHeuristic {
+    costs: HashMap<String, Option<u32>>,
+}
+
+impl TdgHeuristic {
+    pub fn new<T: Hash + Eq>(initial_network: &HTN<T>) -> TdgHeuristic {
+        TdgHeuristic::from_domain(&Domain::from_network(initial_network))
+    }
+
+    /// Computes the task costs once for every problem of the domain.
+    pub fn from_domain<T: Hash + Eq>(domain: &Domain<T>) -> TdgHeuristic {
+        let tasks: Vec<(String, &Rc<Task<T>>)> = domain.tasks().map(|(_, t)| (t.get_name(), t)).collect();
+        let mut costs: HashMap<String, Option<u32>> = tasks
+            .iter()
+            .map(|(name, task)| match task.as_ref() {
+                Task::Primitive(a) => (name.clone(), Some(a.cost())),
+                Task::Compound(_) => (name.clone(), None),
+            })
+            .collect();
+        // Costs only decrease, so the fixpoint is reached after finitely many rounds
+        loop {
+            let mut changed = false;
+            for (name, task) in tasks.iter() {
+                if let Task::Compound(c) = task.as_ref() {
+                    let best = c
+                        .methods()
+                        .iter()
+                        .filter_map(|m| network_cost(&costs, &m.decomposition).map(|c| c.saturating_add(m.cost)))
+                        .min();
+                    if best.is_some() && (costs[name].is_none() || best < costs[name]) {
+                        costs.insert(name.clone(), best);
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        TdgHeuristic { costs }
+    }
+
+    /// The minimal cost of any refinement of the task, or `None` if it
+    /// cannot be refined at all.
+    pub fn task_cost(&self, name: &str) -> Option<u32> {
+        self.costs.get(name).cloned().unwrap_or(Some(0))
+    }
+}
+
+fn network_cost<T: Hash + Eq>(costs: &HashMap<String, Option<u32>>, network: &HTN<T>) -> Option<u32> {
+    let mut sum: u32 = 0;
+    for task in network.get_task_ids().into_iter().filter_map(|id| network.get_task(id)) {
+        let name = task.get_name();
+        // Tasks unknown to the decomposition graph cost nothing as far as we know
+        let cost = costs.get(&name).cloned().unwrap_or(Some(0))?;
+        sum = sum.saturating_add(cost);
+    }
+    Some(sum)
+}
+
+impl<T: Hash + Eq> Heuristic<T> for TdgHeuristic {
+    fn estimate(&self, node: &SearchNode<T>) -> Option<u32> {
+        network_cost(&self.costs, &node.network)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hddl::load_problem;
+    use crate::search::{ProgressionSearch, SearchResult, SearchStrategy, TieBreaking};
+    use std::collections::HashSet;
+
+    const DOMAIN: &str = "
+        (define (domain d)
+          (:task deliver) (:task repeat) (:task stuck)
+          (:method m-long :task (deliver) :ordered-subtasks (and (a) (b) (c)))
+          (:method m-short :task (deliver) :ordered-subtasks (and (repeat) (c)))
+          (:method m-again :task (repeat) :ordered-subtasks (and (a) (repeat)))
+          (:method m-done :task (repeat) :subtasks ())
+          (:method m-stuck :task (stuck) :subtasks (stuck))
+          (:action a) (:action b) (:action c))";
+
+    #[test]
+    fn task_cost_test() {
+        let problem = "(define (problem p) (:domain d) (:htn :subtasks (and (deliver) (stuck))))";
+        let (_, htn) = load_problem(DOMAIN, problem).unwrap();
+        let h = TdgHeuristic::new(&htn);
+        assert_eq!(h.task_cost("a"), Some(1));
+        assert_eq!(h.task_cost("repeat"), Some(0));
+        assert_eq!(h.task_cost("deliver"), Some(1));
+        assert_eq!(h.task_cost("stuck"), None);
+        assert_eq!(h.estimate(&SearchNode::new(HashSet::new(), htn)), None);
+    }
+
+    #[test]
+    fn optimal_plan_test() {
+        let problem = "(define (problem p) (:domain d) (:htn :ordered-subtasks (and (deliver) (a))))";
+        let (state, htn) = load_problem(DOMAIN, problem).unwrap();
+        let h = TdgHeuristic::new(&htn);
+        assert_eq!(h.estimate(&SearchNode::new(state.clone(), htn.clone())), Some(2));
+        let mut search = ProgressionSearch::with_strategy(SearchStrategy::AStar(Box::new(h)), TieBreaking::LowestHeuristic);
+        match search.run(state, htn).unwrap() {
+            SearchResult::Solved { plan, .. } => assert_eq!(plan.action_names(), vec!["c", "a"]),
+            x => panic!("expected a solution, got {:?}", x),
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::builder::BuildError;
+use crate::domain::{Domain, Problem};
+use crate::task_network::{CompoundTask, ConditionalEffect, Method, MethodOwner, PrimitiveAction, Task, HTN};
+
+// Tasks refer to each other by name, so that shared and recursive tasks are
+// written once. Task ids of networks are kept as they are, since plans refer
+// to the ids of the initial network.
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct DomainDocument<T> {
+    tasks: Vec<TaskDocument<T>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum TaskDocument<T> {
+    Primitive {
+        name: String,
+        pre: Vec<T>,
+        #[serde(default = "Vec::new")]
+        neg_pre: Vec<T>,
+        add: Vec<T>,
+        del: Vec<T>,
+        #[serde(default = "Vec::new")]
+        when: Vec<ConditionalEffectDocument<T>>,
+        #[serde(default = "unit_cost")]
+        cost: u32,
+    },
+    Compound {
+        name: String,
+        methods: Vec<MethodDocument<T>>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ConditionalEffectDocument<T> {
+    pre: Vec<T>,
+    #[serde(default = "Vec::new")]
+    neg_pre: Vec<T>,
+    add: Vec<T>,
+    del: Vec<T>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MethodDocument<T> {
+    name: String,
+    #[serde(default = "Vec::new")]
+    pre: Vec<T>,
+    network: NetworkDocument,
+    #[serde(default)]
+    cost: u32,
+}
+
+fn unit_cost() -> u32 {
+    1
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct NetworkDocument {
+    tasks: Vec<SubtaskDocument>,
+    orderings: Vec<(u32, u32)>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SubtaskDocument {
+    id: u32,
+    task: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct ProblemDocument<T> {
+    domain: DomainDocument<T>,
+    initial_state: Vec<T>,
+    initial_network: NetworkDocument,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    goal: Option<Vec<T>>,
+}
+
+impl<T: Hash + Eq + Clone> DomainDocument<T> {
+    pub fn new(domain: &Domain<T>) -> DomainDocument<T> {
+        let tasks = domain
+            .tasks()
+            .map(|(_, task)| match task.as_ref() {
+                Task::Primitive(a) => TaskDocument::Primitive {
+                    name: a.name.clone(),
+                    pre: a.preconditions().iter().cloned().collect(),
+                    neg_pre: a.negative_preconditions().iter().cloned().collect(),
+                    add: a.add_effects().iter().cloned().collect(),
+                    del: a.del_effects().iter().cloned().collect(),
+                    when: a
+                        .conditional_effects()
+                        .iter()
+                        .map(|e| ConditionalEffectDocument {
+                            pre: e.condition.iter().cloned().collect(),
+                            neg_pre: e.negative_condition.iter().cloned().collect(),
+                            add: e.add_effects.iter().cloned().collect(),
+                            del: e.del_effects.iter().cloned().collect(),
+                        })
+                        .collect(),
+                    cost: a.cost(),
+                },
+                Task::Compound(c) => TaskDocument::Compound {
+                    name: c.name.clone(),
+                    methods: c
+                        .methods()
+                        .iter()
+                        .map(|m| MethodDocument {
+                            name: m.name.clone(),
+                            pre: m.preconditions.iter().cloned().collect(),
+                            network: NetworkDocument::new(&m.decomposition),
+                            cost: m.cost,
+                        })
+                        .collect(),
+                },
+            })
+            .collect();
+        DomainDocument { tasks }
+    }
+}
+
+impl<T: Hash + Eq> DomainDocument<T> {
+    /// Resolves the task names and checks the decomposition of every method.
+    pub fn into_domain(self) -> Result<Domain<T>, BuildError> {
+        let mut tasks: HashMap<String, Rc<Task<T>>> = HashMap::new();
+        let mut methods = Vec::new();
+        for task in self.tasks {
+            let (name, task) = match task {
+                TaskDocument::Primitive { name, pre, neg_pre, add, del, when, cost } => {
+                    let when = when.into_iter().map(|e| {
+                        ConditionalEffect::new(
+                            e.pre.into_iter().collect(),
+                            e.add.into_iter().collect(),
+                            e.del.into_iter().collect(),
+                        )
+                        .with_negative_condition(e.neg_pre)
+                    });
+                    let action = PrimitiveAction::new(
+                        name.clone(),
+                        pre.into_iter().collect(),
+                        add.into_iter().collect(),
+                        del.into_iter().collect(),
+                    )
+                    .with_negative_preconditions(neg_pre)
+                    .with_conditional_effects(when)
+                    .with_cost(cost);
+                    (name, Task::Primitive(action))
+                }
+                TaskDocument::Compound { name, methods: m } => {
+                    methods.push((name.clone(), m));
+                    (name.clone(), Task::Compound(CompoundTask::new(name, Vec::new())))
+                }
+            };
+            if tasks.insert(name.clone(), Rc::new(task)).is_some() {
+                return Err(BuildError::DuplicateTask(name));
+            }
+        }
+        for (task, documents) in methods {
+            for method in documents {
+                let network = format!("method `{}`", method.name);
+                let decomposition = method.network.to_htn(&network, &tasks)?;
+                if let Task::Compound(c) = tasks[&task].as_ref() {
+                    let method = Method::new(method.name, decomposition)
+                        .with_preconditions(method.pre)
+                        .with_cost(method.cost);
+                    c.add_method(method);
+                }
+            }
+        }
+        let owner = MethodOwner::new(tasks.values().cloned());
+        let domain = Domain::new(tasks.into_values())?.with_owner(Rc::new(owner));
+        domain.validate()?;
+        Ok(domain)
+    }
+}
+
+impl NetworkDocument {
+    fn new<T: Hash + Eq>(network: &HTN<T>) -> NetworkDocument {
+        let mut ids: Vec<u32> = network.get_task_ids().into_iter().collect();
+        ids.sort_unstable();
+        let tasks = ids
+            .into_iter()
+            .filter_map(|id| network.get_task(id).map(|t| SubtaskDocument { id, task: t.get_name() }))
+            .collect();
+        let mut orderings = network.get_orderings();
+        orderings.sort_unstable();
+        NetworkDocument { tasks, orderings }
+    }
+
+    fn to_htn<T: Hash + Eq>(
+        &self,
+        network: &str,
+        tasks: &HashMap<String, Rc<Task<T>>>,
+    ) -> Result<HTN<T>, BuildError> {
+        let mut mappings = HashMap::new();
+        for subtask in self.tasks.iter() {
+            let task = tasks
+                .get(&subtask.task)
+                .ok_or_else(|| BuildError::UndefinedTask(subtask.task.clone()))?;
+            if mappings.insert(subtask.id, Rc::clone(task)).is_some() {
+                return Err(BuildError::DuplicateSubtask {
+                    network: network.to_string(),
+                    id: subtask.id.to_string(),
+                });
+            }
+        }
+        let ids: HashSet<u32> = mappings.keys().copied().collect();
+        Ok(HTN::new(ids, self.orderings.clone(), mappings))
+    }
+}
+
+impl<T: Hash + Eq + Clone> ProblemDocument<T> {
+    pub fn new(problem: &Problem<T>) -> ProblemDocument<T> {
+        ProblemDocument {
+            domain: DomainDocument::new(&Domain::from_network(&problem.initial_network)),
+            initial_state: problem.initial_state.iter().cloned().collect(),
+            initial_network: NetworkDocument::new(&problem.initial_network),
+            goal: problem.goal.as_ref().map(|g| g.iter().cloned().collect()),
+        }
+    }
+}
+
+impl<T: Hash + Eq> ProblemDocument<T> {
+    pub fn into_problem(self) -> Result<Problem<T>, BuildError> {
+        let domain = self.domain.into_domain()?;
+        let tasks: HashMap<String, Rc<Task<T>>> = domain
+            .tasks()
+            .map(|(_, task)| (task.get_name(), Rc::clone(task)))
+            .collect();
+        let mut network = self.initial_network.to_htn("the initial network", &tasks)?;
+        network.validate()?;
+        if let Some(owner) = domain.owner() {
+            network = network.with_owner(Rc::clone(owner));
+        }
+        Ok(Problem {
+            initial_state: self.initial_state.into_iter().collect(),
+            initial_network: network,
+            goal: self.goal.map(|g| g.into_iter().collect()),
+        })
+    }
+}
@@ -0,0 +1,42 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::builder::BuildError;
+
+#[derive(Debug)]
+pub enum JsonError {
+    /// The text is not JSON of the expected shape
+    Syntax(serde_json::Error),
+    /// The document does not describe a valid domain or task network
+    Invalid(BuildError),
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonError::Syntax(e) => write!(f, "malformed JSON: {}", e),
+            JsonError::Invalid(e) => e.fmt(f),
+        }
+    }
+}
+
+impl Error for JsonError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            JsonError::Syntax(e) => Some(e),
+            JsonError::Invalid(e) => Some(e),
+        }
+    }
+}
+
+impl From<serde_json::Error> for JsonError {
+    fn from(e: serde_json::Error) -> Self {
+        JsonError::Syntax(e)
+    }
+}
+
+impl From<BuildError> for JsonError {
+    fn from(e: BuildError) -> Self {
+        JsonError::Invalid(e)
+    }
+}
@@ -0,0 +1,178 @@
+mod document;
+mod error;
+
+use std::hash::Hash;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::domain::{Domain, Problem};
+use crate::search::SearchResult;
+use document::{DomainDocument, ProblemDocument};
+
+pub use error::JsonError;
+
+/// Writes every task of the domain with its methods or its precondition and
+/// effects. Tasks refer to each other by name.
+pub fn write_domain<T: Hash + Eq + Clone + Serialize>(domain: &Domain<T>) -> Result<String, JsonError> {
+    Ok(serde_json::to_string_pretty(&DomainDocument::new(domain))?)
+}
+
+pub fn read_domain<T: Hash + Eq + DeserializeOwned>(json: &str) -> Result<Domain<T>, JsonError> {
+    let document: DomainDocument<T> = serde_json::from_str(json)?;
+    Ok(document.into_domain()?)
+}
+
+/// Writes the problem together with the domain of its initial network. The
+/// task ids of the initial network are kept, so plans for the problem stay
+/// valid for the problem read back.
+pub fn write_problem<T: Hash + Eq + Clone + Serialize>(problem: &Problem<T>) -> Result<String, JsonError> {
+    Ok(serde_json::to_string_pretty(&ProblemDocument::new(problem))?)
+}
+
+pub fn read_problem<T: Hash + Eq + DeserializeOwned>(json: &str) -> Result<Problem<T>, JsonError> {
+    let document: ProblemDocument<T> = serde_json::from_str(json)?;
+    Ok(document.into_problem()?)
+}
+
+pub fn write_result(result: &SearchResult) -> Result<String, JsonError> {
+    Ok(serde_json::to_string_pretty(result)?)
+}
+
+pub fn read_result(json: &str) -> Result<SearchResult, JsonError> {
+    Ok(serde_json::from_str(json)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::{BuildError, DomainBuilder, NetworkBuilder};
+    use crate::example::create_state_problem_instance;
+    use crate::search::{Limit, ProgressionSearch};
+    use crate::task_network::{ConditionalEffect, HtnError, Task, HTN};
+    use crate::verification::PlanVerifier;
+    use std::collections::HashSet;
+
+    fn construction_problem() -> Problem<String> {
+        let state = HashSet::from(["sunny".to_string()]);
+        Problem::new(state, create_state_problem_instance()).with_goal(["obtained_permit".to_string()])
+    }
+
+    #[test]
+    fn problem_round_trip_test() {
+        let problem = construction_problem();
+        let json = write_problem(&problem).unwrap();
+        let result: Problem<String> = read_problem(&json).unwrap();
+        assert_eq!(result.initial_state, problem.initial_state);
+        assert_eq!(result.goal, problem.goal);
+        let (network, expected) = (&result.initial_network, &problem.initial_network);
+        assert_eq!(network.get_task_ids(), expected.get_task_ids());
+        assert!(HTN::is_isomorphic(network, expected));
+        for id in expected.get_task_ids() {
+            assert_eq!(network.get_task(id), expected.get_task(id));
+        }
+        let Some(Task::Primitive(permit)) = network.get_task(1) else {
+            panic!("`ObtainPermit` should be primitive");
+        };
+        assert_eq!(permit.add_effects(), &HashSet::from(["obtained_permit".to_string()]));
+        let Some(Task::Primitive(hire)) = network.get_task(2) else {
+            panic!("`HireBuilder` should be primitive");
+        };
+        assert_eq!(hire.preconditions(), &HashSet::from(["obtained_permit".to_string()]));
+        let Some(Task::Compound(construct)) = network.get_task(3) else {
+            panic!("`Construct` should be compound");
+        };
+        let decomposition = &construct.methods()[0].decomposition;
+        assert_eq!(decomposition.get_task_ids(), HashSet::from([1, 2, 3, 4, 5]));
+        let mut orderings = decomposition.get_orderings();
+        orderings.sort();
+        assert_eq!(orderings, vec![(1, 2), (2, 3), (2, 4), (3, 5), (4, 5)]);
+
+        // Plans of either problem, with their instance ids, solve the other
+        let solve = |problem| match ProgressionSearch::new().solve(problem) {
+            Ok(SearchResult::Solved { plan, .. }) => plan,
+            x => panic!("unexpected result {:?}", x),
+        };
+        let plan = solve(problem.clone());
+        let verifier = PlanVerifier::new(&result.initial_state, &result.initial_network);
+        assert_eq!(verifier.verify_plan(&plan), Ok(()));
+        let plan = solve(result.clone());
+        let verifier = PlanVerifier::new(&problem.initial_state, &problem.initial_network);
+        assert_eq!(verifier.verify_plan(&plan), Ok(()));
+    }
+
+    #[test]
+    fn domain_round_trip_test() {
+        let domain = Domain::from_network(&create_state_problem_instance());
+        let result: Domain<String> = read_domain(&write_domain(&domain).unwrap()).unwrap();
+        assert_eq!(result.len(), domain.len());
+        for ((i, x), (j, y)) in result.tasks().zip(domain.tasks()) {
+            assert_eq!((i, x), (j, y));
+        }
+        let id = result.task_id("Construct").unwrap();
+        assert_eq!(result.methods(id).unwrap()[0].name, "method-01");
+
+        let domain = DomainBuilder::<String>::new()
+            .primitive("a", [], [], [])
+            .action_cost("a", 4)
+            .negative_preconditions("a", ["q".to_string()])
+            .conditional_effect(
+                "a",
+                ConditionalEffect::new(HashSet::from(["p".to_string()]), HashSet::from(["r".to_string()]), HashSet::new())
+                    .with_negative_condition(["q".to_string()]),
+            )
+            .compound("t")
+            .method("t", "m", NetworkBuilder::new().task("x", "a"))
+            .method_cost("t", "m", 3)
+            .method_preconditions("t", "m", ["p".to_string()])
+            .domain()
+            .unwrap();
+        let result: Domain<String> = read_domain(&write_domain(&domain).unwrap()).unwrap();
+        let Some(Task::Primitive(a)) = result.get("a").map(|x| x.as_ref()) else {
+            panic!("`a` should be primitive");
+        };
+        assert_eq!(a.cost(), 4);
+        assert_eq!(a.negative_preconditions(), &HashSet::from(["q".to_string()]));
+        let effect = &a.conditional_effects()[0];
+        assert_eq!(effect.negative_condition, HashSet::from(["q".to_string()]));
+        assert_eq!(effect.add_effects, HashSet::from(["r".to_string()]));
+        let methods = result.methods(result.task_id("t").unwrap()).unwrap();
+        assert_eq!(methods[0].cost, 3);
+        assert_eq!(methods[0].preconditions, HashSet::from(["p".to_string()]));
+        // Costs may be left out
+        let json = r#"{"tasks": [{"kind": "primitive", "name": "a", "pre": [], "add": [], "del": []}]}"#;
+        let result: Domain<String> = read_domain(json).unwrap();
+        let Some(Task::Primitive(a)) = result.get("a").map(|x| x.as_ref()) else {
+            panic!("`a` should be primitive");
+        };
+        assert_eq!(a.cost(), 1);
+    }
+
+    #[test]
+    fn result_round_trip_test() {
+        let solved = ProgressionSearch::new().solve(construction_problem()).unwrap();
+        assert!(matches!(solved, SearchResult::Solved { .. }));
+        for result in [solved, SearchResult::Unsolvable, SearchResult::LimitReached(Limit::Timeout)] {
+            assert_eq!(read_result(&write_result(&result).unwrap()).unwrap(), result);
+        }
+        let json = write_result(&SearchResult::LimitReached(Limit::FringeSize)).unwrap();
+        assert_eq!(json, "{\n  \"limit_reached\": \"fringe_size\"\n}");
+    }
+
+    #[test]
+    fn invalid_document_test() {
+        assert!(matches!(read_domain::<String>("{\"tasks\": 1}"), Err(JsonError::Syntax(_))));
+        let undefined = r#"{"tasks": [{"kind": "compound", "name": "t", "methods": [
+            {"name": "m", "network": {"tasks": [{"id": 1, "task": "u"}], "orderings": []}}]}]}"#;
+        assert!(matches!(
+            read_domain::<String>(undefined),
+            Err(JsonError::Invalid(BuildError::UndefinedTask(x))) if x == "u"
+        ));
+        let dangling = r#"{"domain": {"tasks": [{"kind": "primitive", "name": "a", "pre": [], "add": [], "del": []}]},
+            "initial_state": [], "initial_network": {"tasks": [{"id": 1, "task": "a"}], "orderings": [[1, 2]]}}"#;
+        assert!(matches!(
+            read_problem::<String>(dangling),
+            Err(JsonError::Invalid(BuildError::Htn(HtnError::DanglingOrdering { before: 1, after: 2 })))
+        ));
+    }
+}
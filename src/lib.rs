@@ -1,6 +1,19 @@
+mod error;
 mod task_network;
 mod search;
+mod plan;
+mod domain;
+pub mod builder;
+pub mod grounding;
+pub mod hddl;
+pub mod heuristics;
+pub mod json;
+pub mod verification;
+#[cfg(test)]
 mod example;
 
+pub use error::Error;
 pub use task_network::*;
-pub use search::*;
\ No newline at end of file
+pub use search::*;
+pub use plan::*;
+pub use domain::*;
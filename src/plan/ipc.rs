@@ -0,0 +1,171 @@
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Write};
+
+use super::{Decomposition, Plan, PlanAction};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanFormatError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for PlanFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for PlanFormatError {}
+
+/// Writes a plan in the IPC 2020 hierarchical plan format: the numbered
+/// actions, the `root` line and one `id task -> method subtask-ids` line per
+/// decomposition, enclosed in `==>` and `<==`.
+pub fn write_ipc_plan<W: Write>(plan: &Plan, out: &mut W) -> io::Result<()> {
+    writeln!(out, "==>")?;
+    for action in plan.actions.iter() {
+        writeln!(out, "{} {}", action.id, action.name)?;
+    }
+    write!(out, "root")?;
+    for id in plan.root.iter() {
+        write!(out, " {}", id)?;
+    }
+    writeln!(out)?;
+    for d in plan.decompositions.iter() {
+        write!(out, "{} {} -> {}", d.id, d.task, d.method)?;
+        for id in d.subtasks.iter() {
+            write!(out, " {}", id)?;
+        }
+        writeln!(out)?;
+    }
+    writeln!(out, "<==")
+}
+
+/// Reads a plan in the IPC 2020 hierarchical plan format. Text outside the
+/// `==>`/`<==` block and `;` comments are ignored. Method names run up to the
+/// first subtask id, so that ground names like `m-drive truck-0 city-1` are
+/// kept whole.
+pub fn parse_ipc_plan(src: &str) -> Result<Plan, PlanFormatError> {
+    let mut plan = Plan::default();
+    let mut lines = src.lines().enumerate().map(|(i, l)| (i + 1, l.split(';').next().unwrap().trim()));
+    let error = |line, message: &str| PlanFormatError {
+        line,
+        message: message.to_string(),
+    };
+    let parse_id = |line, token: &str| token.parse::<u32>().map_err(|_| error(line, "expected a task id"));
+    if !lines.any(|(_, l)| l == "==>") {
+        return Err(error(src.lines().count(), "missing `==>`"));
+    }
+    let mut root_seen = false;
+    for (line, text) in lines {
+        if text.is_empty() {
+            continue;
+        }
+        if text == "<==" {
+            return if root_seen || plan.decompositions.is_empty() {
+                Ok(plan)
+            } else {
+                Err(error(line, "missing `root` line"))
+            };
+        }
+        let mut tokens = text.split_whitespace();
+        let first = tokens.next().unwrap();
+        if first == "root" {
+            plan.root = tokens.map(|x| parse_id(line, x)).collect::<Result<_, _>>()?;
+            root_seen = true;
+            continue;
+        }
+        let id = parse_id(line, first)?;
+        let rest: Vec<&str> = tokens.collect();
+        match rest.iter().position(|x| *x == "->") {
+            None if root_seen => return Err(error(line, "expected a decomposition")),
+            None if rest.is_empty() => return Err(error(line, "missing action name")),
+            None => plan.actions.push(PlanAction {
+                id,
+                name: rest.join(" "),
+            }),
+            Some(arrow) => {
+                let after = &rest[arrow + 1..];
+                let split = after.iter().position(|x| x.parse::<u32>().is_ok()).unwrap_or(after.len());
+                if split == 0 {
+                    return Err(error(line, "missing method name"));
+                }
+                plan.decompositions.push(Decomposition {
+                    id,
+                    task: rest[..arrow].join(" "),
+                    method: after[..split].join(" "),
+                    subtasks: after[split..]
+                        .iter()
+                        .map(|x| parse_id(line, x))
+                        .collect::<Result<_, _>>()?,
+                });
+            }
+        }
+    }
+    Err(error(src.lines().count(), "missing `<==`"))
+}
+
+impl Plan {
+    pub fn to_ipc_string(&self) -> String {
+        let mut buffer = Vec::new();
+        write_ipc_plan(self, &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan::{Decomposition, PlanAction};
+
+    #[test]
+    fn ipc_format_test() {
+        let action = |id, name: &str| PlanAction { id, name: name.to_string() };
+        let plan = Plan::new(
+            Vec::from([
+                action(0, "ObtainPermit"),
+                action(1, "HireBuilder"),
+                action(4, "BuildFoundation"),
+                action(3, "PayBuilder"),
+            ]),
+            Vec::from([0, 1, 2, 3]),
+            Vec::from([Decomposition {
+                id: 2,
+                task: "Construct".to_string(),
+                method: "method-01".to_string(),
+                subtasks: Vec::from([4]),
+            }]),
+        );
+        let expected = "==>\n0 ObtainPermit\n1 HireBuilder\n4 BuildFoundation\n3 PayBuilder\n\
+                        root 0 1 2 3\n2 Construct -> method-01 4\n<==\n";
+        assert_eq!(plan.to_ipc_string(), expected);
+    }
+
+    #[test]
+    fn parse_ipc_test() {
+        let src = "found plan\n==>\n0 drive truck-0 city-1 city-2 ; comment\n\nroot 1\n\
+                   1 get-to truck-0 city-2 -> m-drive-to 0\n<==\n";
+        let plan = parse_ipc_plan(src).unwrap();
+        assert_eq!(plan.actions[0].name, "drive truck-0 city-1 city-2");
+        assert_eq!(plan.root, vec![1]);
+        assert_eq!(plan.decompositions[0].task, "get-to truck-0 city-2");
+        assert_eq!(plan.decompositions[0].subtasks, vec![0]);
+        assert_eq!(parse_ipc_plan(&plan.to_ipc_string()), Ok(plan));
+
+        let src = "==>\n0 drive truck-0 city-1 city-2\nroot 1\n\
+                   1 get-to truck-0 city-2 -> m-drive-to truck-0 city-1 city-2 0\n<==\n";
+        let plan = parse_ipc_plan(src).unwrap();
+        assert_eq!(plan.decompositions[0].method, "m-drive-to truck-0 city-1 city-2");
+        assert_eq!(plan.decompositions[0].subtasks, vec![0]);
+        assert_eq!(parse_ipc_plan(&plan.to_ipc_string()), Ok(plan));
+        assert_eq!(parse_ipc_plan("==>\nroot 1\n1 t -> 0\n<==").unwrap_err().message, "missing method name");
+
+        assert_eq!(parse_ipc_plan("==>\nx move\n<==").unwrap_err().line, 2);
+        assert_eq!(parse_ipc_plan("==>\n0 move\n").unwrap_err().message, "missing `<==`");
+    }
+
+    #[test]
+    fn empty_plan_test() {
+        assert_eq!(Plan::default().to_ipc_string(), "==>\nroot\n<==\n");
+    }
+}
@@ -0,0 +1,5 @@
+mod ipc;
+mod structure;
+
+pub use ipc::{parse_ipc_plan, write_ipc_plan, PlanFormatError};
+pub use structure::{Decomposition, Plan, PlanAction};
@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// A primitive action of a plan, identified by its task instance id.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanAction {
+    pub id: u32,
+    pub name: String,
+}
+
+/// The method applied to a compound task instance and the ids of the
+/// subtask instances it introduced.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Decomposition {
+    pub id: u32,
+    pub task: String,
+    pub method: String,
+    pub subtasks: Vec<u32>,
+}
+
+/// A hierarchical plan: the executed actions in order, the task instances of
+/// the initial network and every decomposition leading to the actions.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Plan {
+    pub actions: Vec<PlanAction>,
+    pub root: Vec<u32>,
+    pub decompositions: Vec<Decomposition>,
+}
+
+impl Plan {
+    pub fn new(actions: Vec<PlanAction>, root: Vec<u32>, decompositions: Vec<Decomposition>) -> Plan {
+        Plan {
+            actions,
+            root,
+            decompositions,
+        }
+    }
+
+    pub fn action_names(&self) -> Vec<String> {
+        self.actions.iter().map(|x| x.name.clone()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.actions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// The deepest nesting of decompositions below the root tasks.
+    pub fn depth(&self) -> usize {
+        let mut depths: HashMap<u32, usize> = self.root.iter().map(|x| (*x, 0)).collect();
+        let mut result = 0;
+        for d in self.decompositions.iter() {
+            let depth = depths.get(&d.id).cloned().unwrap_or(0) + 1;
+            result = result.max(depth);
+            depths.extend(d.subtasks.iter().map(|x| (*x, depth)));
+        }
+        result
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use super::search_node::SearchNode;
+use crate::task_network::HTN;
+
+/// Remembers the (state, task network) pairs seen so far. They are looked
+/// up by hashes that do not depend on task ids or fact order, and told apart
+/// exactly when their hashes collide.
+#[derive(Debug)]
+pub struct ClosedList<T: Hash + Eq> {
+    // Lowest cost and depth with which each pair has been reached
+    seen: NodeMap<T, (u32, usize)>,
+}
+
+impl<T: Hash + Eq> Default for ClosedList<T> {
+    fn default() -> Self {
+        ClosedList { seen: NodeMap::new() }
+    }
+}
+
+impl<T: Hash + Eq + Clone> ClosedList<T> {
+    pub fn new() -> ClosedList<T> {
+        ClosedList::default()
+    }
+
+    /// Records the node and returns whether it is new, or reached at a lower
+    /// cost than before.
+    pub fn insert(&mut self, node: &SearchNode<T>) -> bool {
+        self.insert_at_depth(node, 0)
+    }
+
+    /// Like `insert`, but the node is only a duplicate if the pair was also
+    /// reached at no greater depth, so that depth bounds cut off no more than
+    /// before.
+    pub fn insert_at_depth(&mut self, node: &SearchNode<T>, depth: usize) -> bool {
+        let key = node_key(node);
+        let g = node.cost;
+        match self.seen.get_mut(key, node) {
+            Some((best, shallowest)) if *best <= g && *shallowest <= depth => false,
+            Some((best, shallowest)) => {
+                *best = (*best).min(g);
+                *shallowest = (*shallowest).min(depth);
+                true
+            }
+            None => {
+                self.seen.push(key, node, (g, depth));
+                true
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.len() == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.seen.clear();
+    }
+}
+
+// A state and task network with the value recorded for them
+type Entry<T, V> = (HashSet<T>, HTN<T>, V);
+
+// Values for (state, task network) pairs, bucketed by `node_key`. Within a
+// bucket, entries are kept in the order they were pushed.
+#[derive(Debug)]
+pub(crate) struct NodeMap<T: Hash + Eq, V> {
+    buckets: HashMap<(u64, u64), Vec<Entry<T, V>>>,
+    len: usize,
+}
+
+impl<T: Hash + Eq, V> NodeMap<T, V> {
+    pub fn new() -> NodeMap<T, V> {
+        NodeMap { buckets: HashMap::new(), len: 0 }
+    }
+
+    pub fn contains(&self, key: (u64, u64), node: &SearchNode<T>) -> bool {
+        self.buckets.get(&key).is_some_and(|bucket| {
+            bucket.iter().any(|(state, network, _)| *state == node.state && network.is_equivalent(&node.network))
+        })
+    }
+
+    pub fn get_mut(&mut self, key: (u64, u64), node: &SearchNode<T>) -> Option<&mut V> {
+        self.buckets
+            .get_mut(&key)?
+            .iter_mut()
+            .find(|(state, network, _)| *state == node.state && network.is_equivalent(&node.network))
+            .map(|(_, _, value)| value)
+    }
+
+    pub fn push(&mut self, key: (u64, u64), node: &SearchNode<T>, value: V)
+    where
+        T: Clone,
+    {
+        self.buckets.entry(key).or_default().push((node.state.clone(), node.network.clone(), value));
+        self.len += 1;
+    }
+
+    // Removes the entry pushed last under the key
+    pub fn pop(&mut self, key: (u64, u64)) {
+        if let Some(bucket) = self.buckets.get_mut(&key) {
+            if bucket.pop().is_some() {
+                self.len -= 1;
+            }
+            if bucket.is_empty() {
+                self.buckets.remove(&key);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn clear(&mut self) {
+        self.buckets.clear();
+        self.len = 0;
+    }
+}
+
+// Identifies the node's state and task network up to task ids
+pub(crate) fn node_key<T: Hash + Eq>(node: &SearchNode<T>) -> (u64, u64) {
+    (state_hash(node), node.network.canonical_hash())
+}
+
+fn state_hash<T: Hash + Eq>(node: &SearchNode<T>) -> u64 {
+    let mut facts: Vec<u64> = node
+        .state
+        .iter()
+        .map(|fact| {
+            let mut hasher = DefaultHasher::new();
+            fact.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect();
+    facts.sort_unstable();
+    let mut hasher = DefaultHasher::new();
+    facts.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hddl::load_problem;
+    use crate::task_network::Task;
+
+    #[test]
+    fn commuting_actions_test() {
+        let domain = "
+            (define (domain d) (:predicates (p) (q))
+              (:action a :effect (p)) (:action b :effect (q)))";
+        let problem = "(define (problem p) (:domain d) (:htn :subtasks (and (a) (b) (a))))";
+        let (state, htn) = load_problem(domain, problem).unwrap();
+        let root = SearchNode::new(state, htn);
+        let mut closed = ClosedList::new();
+        assert!(closed.insert(&root));
+        assert!(!closed.insert(&root));
+        let apply = |node: &SearchNode<String>, name: &str| {
+            let id = node
+                .network
+                .get_task_ids()
+                .into_iter()
+                .find(|x| node.network.get_task(*x).unwrap().get_name() == name)
+                .unwrap();
+            match node.network.get_task(id).unwrap() {
+                Task::Primitive(action) => node.apply_action(id, action).unwrap(),
+                Task::Compound(_) => unreachable!(),
+            }
+        };
+        let ab = apply(&apply(&root, "a"), "b");
+        let ba = apply(&apply(&root, "b"), "a");
+        assert!(closed.insert(&ab));
+        assert!(!closed.insert(&ba));
+        let aa = apply(&apply(&root, "a"), "a");
+        assert!(closed.insert(&aa));
+        assert_eq!(closed.len(), 3);
+
+        // Deeper revisits are duplicates, shallower ones are not
+        assert!(!closed.insert_at_depth(&aa, 1));
+        let b = apply(&root, "b");
+        assert!(closed.insert_at_depth(&b, 2));
+        assert!(!closed.insert_at_depth(&b, 3));
+        assert!(closed.insert_at_depth(&b, 1));
+
+        // A cheaper but deeper revisit keeps the shallowest depth seen
+        let mut cheap = b.clone();
+        cheap.cost = 0;
+        assert!(closed.insert_at_depth(&cheap, 5));
+        assert!(!closed.insert_at_depth(&b, 1));
+        assert!(!closed.insert_at_depth(&cheap, 1));
+    }
+}
@@ -0,0 +1,237 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::time::Instant;
+
+use super::closed_list::{node_key, NodeMap};
+use crate::error::Error;
+use super::limits::Limit;
+use super::progression_search::ProgressionSearch;
+use super::search_node::SearchNode;
+use super::search_result::SearchResult;
+use super::strategy::DepthBound;
+
+// A node on the current path with the successors not explored yet
+struct Frame<T: Hash + Eq> {
+    key: Option<(u64, u64)>,
+    pending: Vec<SearchNode<T>>,
+}
+
+/// Depth-first exploration that keeps only the current path, optionally
+/// bounded and iteratively deepened. Nodes repeating the state and task
+/// network of one of their ancestors are pruned, as any solution through
+/// them has a shorter counterpart. With duplicate detection, so are nodes
+/// reached before at no higher cost and depth.
+pub(crate) struct DepthFirst<T: Hash + Eq> {
+    root: SearchNode<T>,
+    bound: Option<DepthBound>,
+    max_limit: usize,
+    iterative: bool,
+    frames: Vec<Frame<T>>,
+    // The nodes of the current path; each frame's entry is the last one
+    // pushed under its key
+    path: NodeMap<T, ()>,
+    // Whether the bound cut off a node in the current iteration
+    cutoff: bool,
+}
+
+impl<T: Hash + Eq + Clone + Debug> DepthFirst<T> {
+    pub fn new(root: SearchNode<T>, bound: Option<DepthBound>, iterative: bool) -> DepthFirst<T> {
+        let max_limit = bound.map_or(usize::MAX, |b| b.limit());
+        // Iterative deepening starts with the smallest bound
+        let bound = if iterative { bound.map(|b| b.with_limit(0)) } else { bound };
+        let frames = vec![Frame { key: None, pending: vec![root.clone()] }];
+        DepthFirst { root, bound, max_limit, iterative, frames, path: NodeMap::new(), cutoff: false }
+    }
+
+    fn pending(&self) -> usize {
+        self.frames.iter().map(|f| f.pending.len()).sum()
+    }
+
+    // Checks the node against the closed list, if duplicate detection is on.
+    // Under a decomposition depth bound, the depth left to the tasks of a
+    // network depends on the path to it, so revisits are always explored.
+    fn is_new(&self, search: &mut ProgressionSearch<T>, node: &SearchNode<T>, depth: Option<(usize, usize)>) -> bool {
+        if let Some(DepthBound::DecompositionDepth(_)) = self.bound {
+            return true;
+        }
+        match search.closed_mut() {
+            Some(closed) => closed.insert_at_depth(node, depth.map_or(0, |(d, _)| d)),
+            None => true,
+        }
+    }
+
+    // Explores until the next goal node, or until the search space within
+    // the largest bound is exhausted
+    pub fn next(&mut self, search: &mut ProgressionSearch<T>, start: Instant) -> Result<SearchResult, Error> {
+        loop {
+            while let Some(frame) = self.frames.last_mut() {
+                let node = match frame.pending.pop() {
+                    Some(node) => node,
+                    None => {
+                        if let Some(key) = self.frames.pop().unwrap().key {
+                            self.path.pop(key);
+                        }
+                        continue;
+                    }
+                };
+                let depth = self.bound.map(|b| (b.measure(&node), b.limit()));
+                if let Some((depth, limit)) = depth {
+                    if depth > limit {
+                        self.cutoff = true;
+                        continue;
+                    }
+                }
+                let key = node_key(&node);
+                if self.path.contains(key, &node) || !self.is_new(search, &node, depth) {
+                    search.statistics_mut().duplicates_pruned += 1;
+                    continue;
+                }
+                if search.is_goal(&node) {
+                    // Shallower goals were reported by earlier iterations
+                    let reported = self.iterative && depth.is_some_and(|(d, l)| d < l);
+                    if !reported {
+                        return Ok(SearchResult::Solved { plan: node.plan(), cost: node.cost });
+                    }
+                    continue;
+                }
+                if let Some(limit) = search.check_limits(start, self.pending()) {
+                    return Ok(SearchResult::LimitReached(limit));
+                }
+                let pending = search.successors(&node)?;
+                self.path.push(key, &node, ());
+                self.frames.push(Frame { key: Some(key), pending });
+                let size = self.pending();
+                let statistics = search.statistics_mut();
+                statistics.peak_fringe_size = statistics.peak_fringe_size.max(size);
+            }
+            let limit = self.bound.map_or(0, |b| b.limit());
+            if !self.cutoff {
+                return Ok(SearchResult::Unsolvable);
+            }
+            if !self.iterative || limit >= self.max_limit {
+                return Ok(SearchResult::LimitReached(Limit::Depth));
+            }
+            self.bound = self.bound.map(|b| b.with_limit(limit + 1));
+            self.cutoff = false;
+            if let Some(closed) = search.closed_mut() {
+                closed.clear();
+            }
+            self.frames.push(Frame { key: None, pending: vec![self.root.clone()] });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::example::create_problem_instance;
+    use crate::hddl::load_problem;
+    use crate::search::{DepthBound, Limit, ProgressionSearch, SearchResult, SearchStrategy, TieBreaking};
+    use std::collections::HashSet;
+
+    const DOMAIN: &str = "
+        (define (domain d) (:predicates (q))
+          (:task deliver) (:task repeat) (:task grow) (:task spin)
+          (:method m-long :task (deliver) :ordered-subtasks (and (a) (b) (c)))
+          (:method m-short :task (deliver) :ordered-subtasks (and (repeat) (c)))
+          (:method m-again :task (repeat) :ordered-subtasks (and (a) (repeat)))
+          (:method m-done :task (repeat) :subtasks ())
+          (:method m-grow :task (grow) :ordered-subtasks (and (grow) (a)))
+          (:method m-stop :task (grow) :subtasks (never))
+          (:method m-spin :task (spin) :ordered-subtasks (and (a) (spin)))
+          (:method m-stop :task (spin) :subtasks (never))
+          (:action a) (:action b) (:action c) (:action never :precondition (q)) (:action make :effect (q)))";
+
+    fn search(strategy: SearchStrategy<String>, task: &str) -> SearchResult {
+        let problem = format!("(define (problem p) (:domain d) (:htn :subtasks ({})))", task);
+        let (state, htn) = load_problem(DOMAIN, &problem).unwrap();
+        ProgressionSearch::with_strategy(strategy, TieBreaking::Fifo).run(state, htn).unwrap()
+    }
+
+    #[test]
+    fn depth_first_test() {
+        let mut search = ProgressionSearch::with_strategy(SearchStrategy::DepthFirst(None), TieBreaking::Fifo);
+        let (result, statistics) = search.run_with_statistics(HashSet::new(), create_problem_instance()).unwrap();
+        assert!(matches!(result, SearchResult::Solved { plan, .. } if plan.len() == 8));
+        assert!(statistics.expanded < 20);
+        assert!(statistics.peak_fringe_size <= 4);
+    }
+
+    #[test]
+    fn loop_protection_test() {
+        // Decomposing spin and applying a leads back to the same node
+        let result = search(SearchStrategy::DepthFirst(None), "spin");
+        assert!(matches!(result, SearchResult::Unsolvable));
+        // grow keeps adding actions, so only a bound stops the search
+        let result = search(SearchStrategy::DepthFirst(Some(DepthBound::DecompositionDepth(6))), "grow");
+        assert!(matches!(result, SearchResult::LimitReached(Limit::Depth)));
+        let result = search(SearchStrategy::IterativeDeepening(DepthBound::DecompositionDepth(6)), "grow");
+        assert!(matches!(result, SearchResult::LimitReached(Limit::Depth)));
+    }
+
+    #[test]
+    fn duplicate_detection_test() {
+        // Every order of a, b and c is tried before giving up on never
+        let problem = "(define (problem p) (:domain d) (:htn :subtasks (and (a) (b) (c) (never))))";
+        let (state, htn) = load_problem(DOMAIN, problem).unwrap();
+        let expanded = |strategy, enabled| {
+            let mut search = ProgressionSearch::with_strategy(strategy, TieBreaking::Fifo);
+            search.set_duplicate_detection(enabled);
+            let (result, statistics) = search.run_with_statistics(state.clone(), htn.clone()).unwrap();
+            assert!(matches!(result, SearchResult::Unsolvable | SearchResult::LimitReached(Limit::Depth)));
+            statistics.expanded
+        };
+        assert_eq!(expanded(SearchStrategy::DepthFirst(None), false), 16);
+        assert_eq!(expanded(SearchStrategy::DepthFirst(None), true), 8);
+        let bound = DepthBound::PlanLength(3);
+        assert!(expanded(SearchStrategy::IterativeDeepening(bound), true) < expanded(SearchStrategy::IterativeDeepening(bound), false));
+
+        // The closed list is cleared between iterations
+        let problem = "(define (problem p) (:domain d) (:htn :subtasks (deliver)))";
+        let (state, htn) = load_problem(DOMAIN, problem).unwrap();
+        let mut search = ProgressionSearch::with_strategy(SearchStrategy::IterativeDeepening(DepthBound::PlanLength(5)), TieBreaking::Fifo);
+        search.set_duplicate_detection(true);
+        let result = search.run(state, htn).unwrap();
+        assert!(matches!(result, SearchResult::Solved { plan, .. } if plan.action_names() == vec!["c"]));
+    }
+
+    #[test]
+    fn hash_collision_test() {
+        // The networks of m1 and m2 only differ in what comes before the
+        // copies of c
+        let domain = "
+            (define (domain h) (:predicates (q)) (:task x)
+              (:method m2 :task (x) :subtasks (and (t1 (a)) (t2 (b)) (t3 (c)) (t4 (c)))
+                :ordering (and (< t1 t3) (< t2 t4)))
+              (:method m1 :task (x) :subtasks (and (t1 (a)) (t2 (b)) (t3 (c)) (t4 (c)))
+                :ordering (and (< t1 t3) (< t2 t3)))
+              (:action a :precondition (q)) (:action b :precondition (q)) (:action c :effect (q)))";
+        let problem = "(define (problem p) (:domain h) (:htn :subtasks (x)))";
+        let (state, htn) = load_problem(domain, problem).unwrap();
+        let strategies = || [SearchStrategy::DepthFirst(None), SearchStrategy::IterativeDeepening(DepthBound::PlanLength(4))];
+        for enabled in [false, true] {
+            for strategy in strategies() {
+                let mut search = ProgressionSearch::with_strategy(strategy, TieBreaking::Fifo);
+                search.set_duplicate_detection(enabled);
+                let result = search.run(state.clone(), htn.clone()).unwrap();
+                assert!(matches!(result, SearchResult::Solved { plan, .. } if plan.len() == 4));
+            }
+        }
+    }
+
+    #[test]
+    fn iterative_deepening_test() {
+        let result = search(SearchStrategy::IterativeDeepening(DepthBound::PlanLength(5)), "deliver");
+        assert!(matches!(result, SearchResult::Solved { plan, .. } if plan.action_names() == vec!["c"]));
+        let result = search(SearchStrategy::DepthFirst(Some(DepthBound::PlanLength(0))), "deliver");
+        assert!(matches!(result, SearchResult::LimitReached(Limit::Depth)));
+
+        // Each solution is reported once, and a c is pruned as it returns to
+        // the node before repeat was decomposed
+        let problem = "(define (problem p) (:domain d) (:htn :subtasks (deliver)))";
+        let (state, htn) = load_problem(DOMAIN, problem).unwrap();
+        let strategy = SearchStrategy::IterativeDeepening(DepthBound::PlanLength(5));
+        let mut search = ProgressionSearch::with_strategy(strategy, TieBreaking::Fifo);
+        let plans: Vec<Vec<String>> = search.solutions(state, htn).map(|p| p.unwrap().action_names()).collect();
+        assert_eq!(plans, vec![vec!["c"], vec!["a", "b", "c"]]);
+    }
+}
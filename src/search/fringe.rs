@@ -0,0 +1,154 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, VecDeque};
+use std::hash::Hash;
+
+use super::search_node::SearchNode;
+use super::strategy::TieBreaking;
+
+pub struct Entry<T: Hash + Eq> {
+    priority: f64,
+    h: u32,
+    counter: u64,
+    tie_breaking: TieBreaking,
+    node: SearchNode<T>,
+}
+
+impl<T: Hash + Eq> Entry<T> {
+    // Nodes that should be expanded first compare as greater, since
+    // `BinaryHeap` is a max-heap
+    fn key(&self) -> (f64, u32, i128) {
+        let counter = self.counter as i128;
+        match self.tie_breaking {
+            TieBreaking::Fifo => (-self.priority, 0, -counter),
+            TieBreaking::Lifo => (-self.priority, 0, counter),
+            TieBreaking::LowestHeuristic => (-self.priority, u32::MAX - self.h, -counter),
+        }
+    }
+}
+
+impl<T: Hash + Eq> PartialEq for Entry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: Hash + Eq> Eq for Entry<T> {}
+
+impl<T: Hash + Eq> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Hash + Eq> Ord for Entry<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (a, b) = (self.key(), other.key());
+        a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2))
+    }
+}
+
+/// A FIFO queue for breadth-first search or a priority queue ordered by
+/// the strategy's priority.
+pub enum Fringe<T: Hash + Eq> {
+    Queue(VecDeque<SearchNode<T>>),
+    Heap {
+        heap: BinaryHeap<Entry<T>>,
+        counter: u64,
+        tie_breaking: TieBreaking,
+    },
+}
+
+impl<T: Hash + Eq> Fringe<T> {
+    pub fn queue() -> Fringe<T> {
+        Fringe::Queue(VecDeque::new())
+    }
+
+    pub fn heap(tie_breaking: TieBreaking) -> Fringe<T> {
+        Fringe::Heap {
+            heap: BinaryHeap::new(),
+            counter: 0,
+            tie_breaking,
+        }
+    }
+
+    pub fn push(&mut self, node: SearchNode<T>, priority: f64, h: u32) {
+        match self {
+            Fringe::Queue(queue) => queue.push_back(node),
+            Fringe::Heap {
+                heap,
+                counter,
+                tie_breaking,
+            } => {
+                *counter += 1;
+                heap.push(Entry {
+                    priority,
+                    h,
+                    counter: *counter,
+                    tie_breaking: *tie_breaking,
+                    node,
+                });
+            }
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<SearchNode<T>> {
+        match self {
+            Fringe::Queue(queue) => queue.pop_front(),
+            Fringe::Heap { heap, .. } => heap.pop().map(|x| x.node),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Fringe::Queue(queue) => queue.len(),
+            Fringe::Heap { heap, .. } => heap.len(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        match self {
+            Fringe::Queue(queue) => queue.clear(),
+            Fringe::Heap { heap, .. } => heap.clear(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task_network::HTN;
+    use std::collections::{HashMap, HashSet};
+
+    fn node(id: u32) -> SearchNode<u32> {
+        SearchNode::new(HashSet::from([id]), HTN::new(HashSet::new(), Vec::new(), HashMap::new()))
+    }
+
+    fn drain(mut fringe: Fringe<u32>) -> Vec<u32> {
+        let mut result = Vec::new();
+        while let Some(n) = fringe.pop() {
+            result.push(*n.state.iter().next().unwrap());
+        }
+        result
+    }
+
+    #[test]
+    fn tie_breaking_test() {
+        let entries = [(1, 2.0, 5), (2, 1.0, 7), (3, 2.0, 3), (4, 1.0, 2), (5, 2.0, 4)];
+        let fill = |tie_breaking| {
+            let mut fringe = Fringe::heap(tie_breaking);
+            for (id, priority, h) in entries {
+                fringe.push(node(id), priority, h);
+            }
+            drain(fringe)
+        };
+        assert_eq!(fill(TieBreaking::Fifo), vec![2, 4, 1, 3, 5]);
+        assert_eq!(fill(TieBreaking::Lifo), vec![4, 2, 5, 3, 1]);
+        assert_eq!(fill(TieBreaking::LowestHeuristic), vec![4, 2, 3, 5, 1]);
+
+        let mut queue = Fringe::queue();
+        for (id, priority, h) in entries {
+            queue.push(node(id), priority, h);
+        }
+        assert_eq!(drain(queue), vec![1, 2, 3, 4, 5]);
+    }
+}
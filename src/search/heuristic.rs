@@ -0,0 +1,10 @@
+use std::hash::Hash;
+
+use super::search_node::SearchNode;
+
+/// Estimates the remaining cost of a search node.
+pub trait Heuristic<T: Hash + Eq> {
+    /// Returns `None` if no solution can be reached from the node, which
+    /// is then pruned.
+    fn estimate(&self, node: &SearchNode<T>) -> Option<u32>;
+}
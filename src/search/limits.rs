@@ -0,0 +1,55 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// Bounds on a single run of `ProgressionSearch`. Every limit is disabled
+/// by default.
+#[derive(Debug, Clone, Default)]
+pub struct SearchLimits {
+    pub timeout: Option<Duration>,
+    pub max_expansions: Option<usize>,
+    pub max_fringe_size: Option<usize>,
+    /// Stops the search once set to `true`, e.g. from another thread
+    pub cancellation: Option<Arc<AtomicBool>>,
+}
+
+/// The limit that stopped a search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Limit {
+    Timeout,
+    Expansions,
+    FringeSize,
+    Cancelled,
+    /// The depth bound of a depth-first strategy cut off part of the search
+    Depth,
+}
+
+impl SearchLimits {
+    pub fn new() -> SearchLimits {
+        SearchLimits::default()
+    }
+
+    // The first limit exceeded after `expansions` nodes have been expanded
+    pub(crate) fn check(&self, start: Instant, expansions: usize, fringe_size: usize) -> Option<Limit> {
+        if let Some(flag) = self.cancellation.as_ref() {
+            if flag.load(Ordering::Relaxed) {
+                return Some(Limit::Cancelled);
+            }
+        }
+        if let Some(timeout) = self.timeout {
+            if start.elapsed() >= timeout {
+                return Some(Limit::Timeout);
+            }
+        }
+        if self.max_expansions.is_some_and(|x| expansions >= x) {
+            return Some(Limit::Expansions);
+        }
+        if self.max_fringe_size.is_some_and(|x| fringe_size > x) {
+            return Some(Limit::FringeSize);
+        }
+        None
+    }
+}
@@ -1,9 +1,24 @@
+mod closed_list;
+mod depth_first;
+mod fringe;
+mod heuristic;
+mod limits;
 mod search_node;
 mod progression_search;
 mod search_result;
+mod solutions;
+mod statistics;
+mod strategy;
 
 use super::task_network::HTN;
-use super::task_network::{Task, PrimitiveAction, CompoundTask, Applicability};
+use super::task_network::{Applicability, HtnError, Method, PrimitiveAction};
 
+pub use closed_list::ClosedList;
+pub use heuristic::Heuristic;
+pub use limits::{Limit, SearchLimits};
+pub use search_node::SearchNode;
 pub use search_result::SearchResult;
-pub use progression_search::ProgressionSearch;
\ No newline at end of file
+pub use solutions::Solutions;
+pub use statistics::SearchStatistics;
+pub use progression_search::ProgressionSearch;
+pub use strategy::{DepthBound, SearchStrategy, TieBreaking};
@@ -1,80 +1,258 @@
-use std::collections::{HashSet, VecDeque};
+use std::collections::HashSet;
 use std::hash::Hash;
+use std::time::Instant;
 
-use crate::task_network::{Task, PrimitiveAction};
+use crate::domain::Problem;
+use crate::error::Error;
+use crate::task_network::Task;
 
 use super::HTN;
+use super::closed_list::ClosedList;
+use super::depth_first::DepthFirst;
+use super::fringe::Fringe;
+use super::limits::{Limit, SearchLimits};
 use super::search_result::SearchResult;
 use super::search_node::SearchNode;
-use super::Applicability;
+use super::solutions::Solutions;
+use super::statistics::SearchStatistics;
+use super::strategy::{SearchStrategy, TieBreaking};
+use super::{Applicability, HtnError};
 
 pub struct ProgressionSearch<T: Hash + Eq>{
-    fringe: VecDeque<SearchNode<T>>
+    fringe: Fringe<T>,
+    strategy: SearchStrategy<T>,
+    closed: Option<ClosedList<T>>,
+    limits: SearchLimits,
+    statistics: SearchStatistics,
+    // Used instead of the fringe by the depth-first strategies
+    depth_first: Option<DepthFirst<T>>,
+    // The goal of the problem being solved
+    goal: Option<HashSet<T>>,
+}
+
+impl <T: Hash + Eq + Clone + std::fmt::Debug> Default for ProgressionSearch<T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl <T: Hash + Eq + Clone + std::fmt::Debug> ProgressionSearch<T> {
     pub fn new() -> ProgressionSearch<T> {
-        ProgressionSearch { fringe: VecDeque::new() }
-    }
-
-    pub fn run(&mut self, initial_state: HashSet<T>, initial_network: HTN<T>) -> SearchResult {
-        let init = SearchNode::new(initial_state, initial_network, Vec::new());
-        self.fringe.push_back(init);
-        while !self.fringe.is_empty() {
-            let n = self.fringe.pop_front().unwrap();
-            if n.is_goal() { return SearchResult::Solved(n.sequence);}
-            let unconstrained = n.network.get_unconstrained_tasks();
-            let u_a: HashSet<u32> = unconstrained.iter().filter(|x| n.network.is_primitive(**x)).cloned().collect();
-            let u_c: HashSet<u32> = unconstrained.iter().filter(|x| !u_a.contains(*x)).cloned().collect();
-            if u_c.is_empty() {
-                for t in u_a.iter(){
-                    let task = n.network.get_task(*t).unwrap();
-                    if let Task::Primitive(a) = task {
-                        if a.is_applicable(&n.state) {
-                            let new_network = n.network.apply_action(*t);
-                            let new_state = a.transition(&n.state);
-                            let mut new_sequence = n.sequence.clone();
-                            new_sequence.push(task.get_name());
-                            let new_search_node = SearchNode::new(
-                                new_state,
-                                new_network,
-                                new_sequence
-                            );
-                            self.fringe.push_back(new_search_node)
-                        }
-                    }
+        ProgressionSearch { fringe: Fringe::queue(), strategy: SearchStrategy::BreadthFirst, closed: None, limits: SearchLimits::default(), statistics: SearchStatistics::default(), depth_first: None, goal: None }
+    }
+
+    pub fn with_strategy(strategy: SearchStrategy<T>, tie_breaking: TieBreaking) -> ProgressionSearch<T> {
+        let fringe = match strategy {
+            SearchStrategy::BreadthFirst => Fringe::queue(),
+            _ => Fringe::heap(tie_breaking),
+        };
+        ProgressionSearch { fringe, strategy, closed: None, limits: SearchLimits::default(), statistics: SearchStatistics::default(), depth_first: None, goal: None }
+    }
+
+    /// Discards nodes whose state and task network have already been reached
+    /// at no higher cost. The depth-first strategies also require the earlier
+    /// visit to be no deeper, and skip the check under a decomposition depth
+    /// bound. Disabled by default.
+    pub fn set_duplicate_detection(&mut self, enabled: bool) {
+        self.closed = if enabled { Some(ClosedList::new()) } else { None };
+    }
+
+    pub fn set_limits(&mut self, limits: SearchLimits) {
+        self.limits = limits;
+    }
+
+    /// Statistics of the last or current run.
+    pub fn statistics(&self) -> &SearchStatistics {
+        &self.statistics
+    }
+
+    // Evaluates the node and adds it to the fringe unless it is a dead end
+    fn push(&mut self, node: SearchNode<T>) {
+        if let Some(closed) = self.closed.as_mut() {
+            if !closed.insert(&node) {
+                self.statistics.duplicates_pruned += 1;
+                return;
+            }
+        }
+        let h = match self.strategy.heuristic() {
+            Some(heuristic) => match heuristic.estimate(&node) {
+                Some(h) => h,
+                None => {
+                    self.statistics.dead_ends_pruned += 1;
+                    return;
                 }
-            } else {
-                let t = u_c.iter().next().unwrap();
-                let task = n.network.get_task(*t).unwrap();
-                if let Task::Compound(c) = task {
-                    for m in c.methods.iter() {
-                        let new_network = n.network.decompose(*t, m);
-                        let new_search_node = SearchNode::new(
-                            n.state.clone(),
-                            new_network,
-                            n.sequence.clone()
-                        );
-                        self.fringe.push_back(new_search_node);
+            },
+            None => 0,
+        };
+        let priority = self.strategy.priority(node.cost, h);
+        self.fringe.push(node, priority, h);
+        self.statistics.peak_fringe_size = self.statistics.peak_fringe_size.max(self.fringe.len());
+    }
+
+    /// Searches for a solution. Fails if a task network met during the
+    /// search is malformed, e.g. a task without a mapping.
+    pub fn run(&mut self, initial_state: HashSet<T>, initial_network: HTN<T>) -> Result<SearchResult, Error> {
+        self.solve(Problem::new(initial_state, initial_network))
+    }
+
+    /// Runs the search and reports what it did alongside the result.
+    pub fn run_with_statistics(
+        &mut self,
+        initial_state: HashSet<T>,
+        initial_network: HTN<T>,
+    ) -> Result<(SearchResult, SearchStatistics), Error> {
+        self.solve_with_statistics(Problem::new(initial_state, initial_network))
+    }
+
+    /// Enumerates the solutions of the problem lazily, continuing the search
+    /// after each goal node. With duplicate detection enabled, solutions
+    /// ending in an already reached state may be skipped.
+    pub fn solutions(&mut self, initial_state: HashSet<T>, initial_network: HTN<T>) -> Solutions<'_, T> {
+        self.solve_all(Problem::new(initial_state, initial_network))
+    }
+
+    /// Like `run`, but only accepts plans whose final state satisfies the
+    /// goal of the problem, if it has one.
+    pub fn solve(&mut self, problem: Problem<T>) -> Result<SearchResult, Error> {
+        Ok(self.solve_with_statistics(problem)?.0)
+    }
+
+    pub fn solve_with_statistics(&mut self, problem: Problem<T>) -> Result<(SearchResult, SearchStatistics), Error> {
+        let start = self.start(problem);
+        let result = self.resume(start)?;
+        Ok((result, self.statistics.clone()))
+    }
+
+    pub fn solve_all(&mut self, problem: Problem<T>) -> Solutions<'_, T> {
+        let start = self.start(problem);
+        Solutions::new(self, start)
+    }
+
+    // Resets the search to the initial node
+    fn start(&mut self, problem: Problem<T>) -> Instant {
+        let start = Instant::now();
+        self.statistics = SearchStatistics::default();
+        self.fringe.clear();
+        if let Some(closed) = self.closed.as_mut() {
+            closed.clear();
+        }
+        self.goal = problem.goal;
+        let root = SearchNode::new(problem.initial_state, problem.initial_network);
+        match self.strategy.depth_bound() {
+            Some((bound, iterative)) => self.depth_first = Some(DepthFirst::new(root, bound, iterative)),
+            None => self.push(root),
+        }
+        start
+    }
+
+    // Searches from the current fringe until the next goal node
+    pub(crate) fn resume(&mut self, start: Instant) -> Result<SearchResult, Error> {
+        let result = match self.depth_first.take() {
+            Some(mut depth_first) => {
+                let result = depth_first.next(self, start);
+                self.depth_first = Some(depth_first);
+                result
+            }
+            None => self.expand_until_goal(start),
+        };
+        self.statistics.elapsed = start.elapsed();
+        result
+    }
+
+    // The network is done and the goal, if any, holds
+    pub(crate) fn is_goal(&self, node: &SearchNode<T>) -> bool {
+        node.is_goal() && self.goal.as_ref().is_none_or(|goal| goal.is_subset(&node.state))
+    }
+
+    pub(crate) fn statistics_mut(&mut self) -> &mut SearchStatistics {
+        &mut self.statistics
+    }
+
+    pub(crate) fn closed_mut(&mut self) -> Option<&mut ClosedList<T>> {
+        self.closed.as_mut()
+    }
+
+    // Checks the limits before expanding another node
+    pub(crate) fn check_limits(&mut self, start: Instant, fringe_size: usize) -> Option<Limit> {
+        let limit = self.limits.check(start, self.statistics.expanded, fringe_size);
+        if limit.is_none() {
+            self.statistics.expanded += 1;
+        }
+        limit
+    }
+
+    // Applies every applicable unconstrained action, or decomposes an
+    // unconstrained compound task with each of its applicable methods
+    pub(crate) fn successors(&mut self, n: &SearchNode<T>) -> Result<Vec<SearchNode<T>>, Error> {
+        let mut result = Vec::new();
+        let unconstrained = n.network.get_unconstrained_tasks();
+        let mut u_a: HashSet<u32> = HashSet::new();
+        let mut u_c: HashSet<u32> = HashSet::new();
+        for t in unconstrained {
+            if n.network.is_primitive(t)? { u_a.insert(t); } else { u_c.insert(t); }
+        }
+        if u_c.is_empty() {
+            for t in u_a.iter(){
+                let task = n.network.get_task(*t).ok_or(HtnError::MissingMapping(*t))?;
+                if let Task::Primitive(a) = task {
+                    if a.is_applicable(&n.state) {
+                        self.statistics.generated += 1;
+                        self.statistics.action_applications += 1;
+                        result.push(n.apply_action(*t, a)?)
                     }
                 }
             }
+        } else {
+            let t = u_c.iter().next().unwrap();
+            let task = n.network.get_task(*t).ok_or(HtnError::MissingMapping(*t))?;
+            if let Task::Compound(c) = task {
+                for m in c.methods().iter().filter(|m| m.is_applicable(&n.state)) {
+                    self.statistics.generated += 1;
+                    self.statistics.decompositions += 1;
+                    result.push(n.decompose(*t, m)?);
+                }
+            }
         }
-        SearchResult::Unsolvable
+        Ok(result)
+    }
+
+    fn expand_until_goal(&mut self, start: Instant) -> Result<SearchResult, Error> {
+        while let Some(n) = self.fringe.pop() {
+            if self.is_goal(&n) { return Ok(SearchResult::Solved { plan: n.plan(), cost: n.cost });}
+            if let Some(limit) = self.check_limits(start, self.fringe.len()) {
+                return Ok(SearchResult::LimitReached(limit));
+            }
+            for child in self.successors(&n)? {
+                self.push(child);
+            }
+        }
+        Ok(SearchResult::Unsolvable)
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::search::{Heuristic, Limit};
+    use crate::task_network::{CompoundTask, ConditionalEffect, Method, PrimitiveAction};
+    use std::collections::HashMap;
+    use std::rc::Rc;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+    use std::time::Duration;
     use crate::example::create_problem_instance;
     use crate::example::create_state_problem_instance;
+    use crate::builder::{DomainBuilder, NetworkBuilder};
+    use crate::domain::Domain;
+    use crate::heuristics::{RelaxedComposition, RelaxedEstimate, TdgHeuristic};
+    use crate::verification::PlanVerifier;
     #[test]
     pub fn hierarchy_correctness_test() {
         let htn = create_problem_instance();
         let mut search = ProgressionSearch::<u32>::new();
-        let result = search.run(HashSet::new(), htn);
-        if let SearchResult::Solved(x) = result {
+        let result = search.run(HashSet::new(), htn).unwrap();
+        if let SearchResult::Solved { plan, .. } = result {
+            let x = plan.action_names();
             assert_eq!(x[2], "BuildFoundation");
             assert_eq!(x[3], "BuildFrame");
             assert_eq!(x[6], "BuildInterior");
@@ -82,12 +260,163 @@ mod test {
         }
     }
 
+    #[test]
+    pub fn decomposition_tree_test() {
+        let htn = create_problem_instance();
+        let mut search = ProgressionSearch::<u32>::new();
+        let result = search.run(HashSet::new(), htn).unwrap();
+        if let SearchResult::Solved { plan, .. } = result {
+            // Instance ids are the ids of the network, fresh ones for subtasks
+            assert_eq!(plan.root, vec![1, 2, 3, 4]);
+            assert_eq!(plan.decompositions.len(), 1);
+            let d = &plan.decompositions[0];
+            assert_eq!((d.id, d.task.as_str(), d.method.as_str()), (3, "Construct", "method-01"));
+            assert_eq!(d.subtasks, vec![5, 6, 7, 8, 9]);
+            let ids: HashSet<u32> = plan.actions.iter().map(|x| x.id).collect();
+            assert_eq!(ids, HashSet::from([1, 2, 4, 5, 6, 7, 8, 9]));
+            assert_eq!(plan.actions[2].id, 5);
+            assert!(plan.to_ipc_string().contains("\n3 Construct -> method-01 5 6 7 8 9\n"));
+        } else {
+            panic!("expected a solution");
+        }
+    }
+
+    // Every primitive task left in the network has to be applied
+    struct RemainingActions;
+
+    impl<T: Hash + Eq> Heuristic<T> for RemainingActions {
+        fn estimate(&self, node: &SearchNode<T>) -> Option<u32> {
+            let ids = node.network.get_task_ids();
+            Some(ids.into_iter().filter(|x| node.network.is_primitive(*x) == Ok(true)).count() as u32)
+        }
+    }
+
+    struct DeadEnd;
+
+    impl<T: Hash + Eq> Heuristic<T> for DeadEnd {
+        fn estimate(&self, _: &SearchNode<T>) -> Option<u32> {
+            None
+        }
+    }
+
+    #[test]
+    pub fn informed_search_test() {
+        let strategies: Vec<SearchStrategy<String>> = vec![
+            SearchStrategy::AStar(Box::new(RemainingActions)),
+            SearchStrategy::WeightedAStar(Box::new(RemainingActions), 2.0),
+            SearchStrategy::GreedyBestFirst(Box::new(RemainingActions)),
+        ];
+        for strategy in strategies {
+            let mut search = ProgressionSearch::with_strategy(strategy, TieBreaking::LowestHeuristic);
+            match search.run(HashSet::new(), create_state_problem_instance()).unwrap() {
+                SearchResult::Solved { plan, .. } => {
+                    let x = plan.action_names();
+                    assert_eq!(x.len(), 8);
+                    assert_eq!(x[0], "ObtainPermit");
+                    assert_eq!(x[7], "PayBuilder");
+                }
+                x => panic!("expected a solution, got {:?}", x),
+            }
+        }
+    }
+
+    #[test]
+    pub fn dead_end_pruning_test() {
+        let mut search = ProgressionSearch::with_strategy(SearchStrategy::AStar(Box::new(DeadEnd)), TieBreaking::Fifo);
+        assert!(matches!(search.run(HashSet::new(), create_problem_instance()).unwrap(), SearchResult::Unsolvable));
+    }
+
+    #[test]
+    pub fn duplicate_detection_test() {
+        let domain = "
+            (define (domain d) (:predicates (q)) (:task spin)
+              (:method m-again :task (spin) :subtasks (spin))
+              (:method m-done :task (spin) :subtasks (b))
+              (:action b :precondition (q)) (:action c :effect (q)))";
+        let problem = "(define (problem p) (:domain d) (:htn :subtasks (spin)))";
+        let (state, htn) = crate::hddl::load_problem(domain, problem).unwrap();
+        // Without duplicate detection this search would never terminate
+        let mut search = ProgressionSearch::new();
+        search.set_duplicate_detection(true);
+        let (result, statistics) = search.run_with_statistics(state, htn).unwrap();
+        assert!(matches!(result, SearchResult::Unsolvable));
+        assert_eq!((statistics.expanded, statistics.generated, statistics.duplicates_pruned), (2, 2, 1));
+        let result = search.run(HashSet::new(), create_state_problem_instance()).unwrap();
+        assert!(matches!(result, SearchResult::Solved { plan, .. } if plan.len() == 8));
+    }
+
+    #[test]
+    pub fn statistics_test() {
+        let mut search = ProgressionSearch::new();
+        let (result, statistics) = search.run_with_statistics(HashSet::new(), create_problem_instance()).unwrap();
+        assert!(matches!(result, SearchResult::Solved { .. }));
+        // ObtainPermit and HireBuilder are unordered, so Construct is reached twice
+        assert_eq!(statistics.decompositions, 2);
+        assert_eq!(statistics.generated, statistics.decompositions + statistics.action_applications);
+        assert!(statistics.expanded > 8);
+        assert!(statistics.peak_fringe_size >= 2);
+        assert_eq!((statistics.duplicates_pruned, statistics.dead_ends_pruned), (0, 0));
+        let json = serde_json::to_value(&statistics).unwrap();
+        assert_eq!(json["expanded"], statistics.expanded);
+        assert!(json["elapsed"].is_object());
+    }
+
+    #[test]
+    pub fn limits_test() {
+        let domain = "
+            (define (domain d) (:predicates (q)) (:task spin)
+              (:method m-again :task (spin) :ordered-subtasks (and (a) (spin)))
+              (:method m-done :task (spin) :subtasks (b))
+              (:action a) (:action b :precondition (q)) (:action c :effect (q)))";
+        let problem = "(define (problem p) (:domain d) (:htn :subtasks (spin)))";
+        // b can never be applied, but the network can be refined forever
+        let (state, htn) = crate::hddl::load_problem(domain, problem).unwrap();
+        let mut search = ProgressionSearch::new();
+        let run = |search: &mut ProgressionSearch<String>, limits: SearchLimits| {
+            search.set_limits(limits);
+            search.run(state.clone(), htn.clone()).unwrap()
+        };
+        let limits = SearchLimits { max_expansions: Some(50), ..SearchLimits::new() };
+        assert!(matches!(run(&mut search, limits), SearchResult::LimitReached(Limit::Expansions)));
+        let limits = SearchLimits { timeout: Some(Duration::from_millis(20)), ..SearchLimits::new() };
+        assert!(matches!(run(&mut search, limits), SearchResult::LimitReached(Limit::Timeout)));
+        let flag = Arc::new(AtomicBool::new(true));
+        let limits = SearchLimits { cancellation: Some(flag.clone()), ..SearchLimits::new() };
+        assert!(matches!(run(&mut search, limits), SearchResult::LimitReached(Limit::Cancelled)));
+
+        // The fringe of breadth-first search grows with every unordered task
+        let mut search = ProgressionSearch::new();
+        search.set_limits(SearchLimits { max_fringe_size: Some(2), ..SearchLimits::new() });
+        let result = search.run(HashSet::new(), create_problem_instance()).unwrap();
+        assert!(matches!(result, SearchResult::LimitReached(Limit::FringeSize)));
+        search.set_limits(SearchLimits::new());
+        assert!(matches!(search.run(HashSet::new(), create_problem_instance()).unwrap(), SearchResult::Solved { .. }));
+    }
+
+    #[test]
+    pub fn malformed_method_test() {
+        let action = Rc::new(Task::Primitive(PrimitiveAction::new(
+            "Act".to_string(),
+            HashSet::new(),
+            HashSet::new(),
+            HashSet::new(),
+        )));
+        // The method's second subtask has no mapping
+        let broken = HTN::new(HashSet::from([1, 2]), vec![(1, 2)], HashMap::from([(1, action)]));
+        let method = Method::new("broken".to_string(), broken);
+        let task = Rc::new(Task::Compound(CompoundTask::new("Task".to_string(), vec![method])));
+        let htn = HTN::new(HashSet::from([1]), vec![], HashMap::from([(1, task)]));
+        let result = ProgressionSearch::<u32>::new().run(HashSet::new(), htn);
+        assert!(matches!(result, Err(Error::Htn(HtnError::MissingMapping(_)))));
+    }
+
     #[test]
     pub fn state_progression_test() {
         let htn = create_state_problem_instance();
         let mut search = ProgressionSearch::<String>::new();
-        let result = search.run(HashSet::new(), htn);
-        if let SearchResult::Solved(x) = result {
+        let result = search.run(HashSet::new(), htn).unwrap();
+        if let SearchResult::Solved { plan, .. } = result {
+            let x = plan.action_names();
             assert_eq!(x[0], "ObtainPermit");
             assert_eq!(x[1], "HireBuilder");
             assert_eq!(x[2], "BuildFoundation");
@@ -96,4 +425,231 @@ mod test {
             assert_eq!(x[7], "PayBuilder");
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    pub fn goal_test() {
+        let domain = DomainBuilder::new()
+            .primitive("a", [], ["p".to_string()], [])
+            .primitive("b", [], ["q".to_string()], [])
+            .compound("t")
+            .method("t", "m-a", NetworkBuilder::new().task("a", "a"))
+            .method("t", "m-b", NetworkBuilder::new().task("b", "b"))
+            .domain()
+            .unwrap();
+        let network = NetworkBuilder::new().task("t", "t").build(&domain).unwrap();
+        let problem = Problem::new(HashSet::new(), network);
+        let strategies = || -> Vec<SearchStrategy<String>> {
+            vec![
+                SearchStrategy::BreadthFirst,
+                SearchStrategy::DepthFirst(None),
+                SearchStrategy::AStar(Box::new(TdgHeuristic::from_domain(&domain))),
+            ]
+        };
+        for strategy in strategies() {
+            let mut search = ProgressionSearch::with_strategy(strategy, TieBreaking::Fifo);
+            match search.solve(problem.clone().with_goal(["q".to_string()])).unwrap() {
+                SearchResult::Solved { plan, .. } => assert_eq!(plan.action_names(), vec!["b"]),
+                x => panic!("unexpected result {:?}", x),
+            }
+            let result = search.solve(problem.clone().with_goal(["p".to_string(), "q".to_string()]));
+            assert!(matches!(result, Ok(SearchResult::Unsolvable)));
+        }
+        // A second problem of the same domain
+        let network = NetworkBuilder::new().task("x", "t").task("y", "t").before("x", "y").build(&domain).unwrap();
+        let problem = Problem::new(HashSet::new(), network).with_goal(["p".to_string(), "q".to_string()]);
+        for strategy in strategies() {
+            let mut search = ProgressionSearch::with_strategy(strategy, TieBreaking::Fifo);
+            match search.solve(problem.clone()).unwrap() {
+                SearchResult::Solved { plan, .. } => assert_eq!(plan.len(), 2),
+                x => panic!("unexpected result {:?}", x),
+            }
+        }
+    }
+
+    #[test]
+    pub fn hash_collision_test() {
+        // m1 and m2 give networks whose task hashes only differ in what
+        // comes before the copies of c
+        let domain = |methods: [(&str, NetworkBuilder); 2]| {
+            let mut domain = DomainBuilder::<String>::new()
+                .primitive("a", ["q".to_string()], [], [])
+                .primitive("b", ["q".to_string()], [], [])
+                .primitive("c", [], ["q".to_string()], [])
+                .compound("x");
+            for (name, network) in methods {
+                domain = domain.method("x", name, network);
+            }
+            domain.build(NetworkBuilder::new().task("x", "x")).unwrap()
+        };
+        let tasks = || NetworkBuilder::new().task("t1", "a").task("t2", "b").task("t3", "c").task("t4", "c");
+        let m1 = ("m1", tasks().before("t1", "t3").before("t2", "t3"));
+        let m2 = ("m2", tasks().before("t1", "t3").before("t2", "t4"));
+        for network in [domain([m1.clone(), m2.clone()]), domain([m2, m1])] {
+            for enabled in [false, true] {
+                let mut search = ProgressionSearch::new();
+                search.set_duplicate_detection(enabled);
+                match search.run(HashSet::new(), network.clone()).unwrap() {
+                    SearchResult::Solved { plan, .. } => assert_eq!(plan.len(), 4),
+                    x => panic!("unexpected result {:?}", x),
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn cost_optimal_test() {
+        // The cheapest refinement has the most actions
+        let domain = |method_cost| {
+            DomainBuilder::<String>::new()
+                .primitive("a", [], [], [])
+                .primitive("b", [], [], [])
+                .primitive("expensive", [], [], [])
+                .action_cost("expensive", 10)
+                .compound("t")
+                .method("t", "m-short", NetworkBuilder::new().task("x", "expensive"))
+                .method(
+                    "t",
+                    "m-long",
+                    NetworkBuilder::new().task("x", "a").task("y", "b").task("z", "a").sequence(["x", "y", "z"]),
+                )
+                .method_cost("t", "m-long", method_cost)
+                .domain()
+                .unwrap()
+        };
+        let solve = |domain: &Domain<String>, strategy| {
+            let network = NetworkBuilder::new().task("t", "t").build(domain).unwrap();
+            let mut search = ProgressionSearch::with_strategy(strategy, TieBreaking::Fifo);
+            search.set_duplicate_detection(true);
+            match search.run(HashSet::new(), network).unwrap() {
+                SearchResult::Solved { plan, cost } => (plan.action_names(), cost),
+                x => panic!("unexpected result {:?}", x),
+            }
+        };
+        let cheap = domain(2);
+        let long = (vec!["a".to_string(), "b".to_string(), "a".to_string()], 5);
+        assert_eq!(solve(&cheap, SearchStrategy::BreadthFirst), (vec!["expensive".to_string()], 10));
+        assert_eq!(solve(&cheap, SearchStrategy::UniformCost), long);
+        assert_eq!(solve(&cheap, SearchStrategy::AStar(Box::new(TdgHeuristic::from_domain(&cheap)))), long);
+        let h = RelaxedComposition::from_domain(&cheap, RelaxedEstimate::Max);
+        assert_eq!(solve(&cheap, SearchStrategy::AStar(Box::new(h))), long);
+        // Counting the method's cost, the single action is cheaper
+        let costly = domain(8);
+        assert_eq!(solve(&costly, SearchStrategy::UniformCost), (vec!["expensive".to_string()], 10));
+        assert_eq!(TdgHeuristic::from_domain(&costly).task_cost("t"), Some(10));
+    }
+
+    #[test]
+    pub fn cost_overflow_test() {
+        let domain = DomainBuilder::<String>::new()
+            .primitive("a", [], [], [])
+            .primitive("b", [], [], [])
+            .action_cost("a", u32::MAX)
+            .action_cost("b", 1)
+            .compound("t")
+            .method("t", "m", NetworkBuilder::new().task("x", "a").task("y", "b").sequence(["x", "y"]))
+            .method_cost("t", "m", u32::MAX)
+            .domain()
+            .unwrap();
+        let network = NetworkBuilder::new().task("t", "t").build(&domain).unwrap();
+        // Costs saturate instead of wrapping around
+        for strategy in [SearchStrategy::UniformCost, SearchStrategy::AStar(Box::new(TdgHeuristic::from_domain(&domain)))] {
+            let mut search = ProgressionSearch::with_strategy(strategy, TieBreaking::Fifo);
+            match search.run(HashSet::new(), network.clone()).unwrap() {
+                SearchResult::Solved { cost, .. } => assert_eq!(cost, u32::MAX),
+                x => panic!("unexpected result {:?}", x),
+            }
+        }
+    }
+
+    #[test]
+    pub fn method_precondition_test() {
+        let domain = DomainBuilder::<String>::new()
+            .primitive("get-car", [], ["car".to_string()], [])
+            .primitive("drive", [], [], [])
+            .primitive("walk", [], [], [])
+            .action_cost("walk", 5)
+            .compound("go")
+            .method("go", "m-drive", NetworkBuilder::new().task("d", "drive"))
+            .method_preconditions("go", "m-drive", ["car".to_string()])
+            .method("go", "m-walk", NetworkBuilder::new().task("w", "walk"))
+            .domain()
+            .unwrap();
+        let solve = |network: NetworkBuilder| {
+            let network = network.build(&domain).unwrap();
+            let mut search = ProgressionSearch::with_strategy(SearchStrategy::UniformCost, TieBreaking::Fifo);
+            match search.run(HashSet::new(), network).unwrap() {
+                SearchResult::Solved { plan, .. } => plan.action_names(),
+                x => panic!("unexpected result {:?}", x),
+            }
+        };
+        assert_eq!(solve(NetworkBuilder::new().task("g", "go")), vec!["walk"]);
+        assert_eq!(solve(NetworkBuilder::new().task("c", "get-car").task("g", "go").before("c", "g")), vec!["get-car", "drive"]);
+        // `go` is decomposed before any action is applied
+        assert_eq!(solve(NetworkBuilder::new().task("g", "go").task("c", "get-car")), vec!["get-car", "walk"]);
+    }
+
+    #[test]
+    pub fn negative_precondition_test() {
+        let domain = DomainBuilder::<String>::new()
+            .primitive("unlock", [], [], ["locked".to_string()])
+            .primitive("enter", [], ["inside".to_string()], [])
+            .negative_preconditions("enter", ["locked".to_string()])
+            .compound("t")
+            .method("t", "m-enter", NetworkBuilder::new().task("e", "enter"))
+            .method("t", "m-unlock", NetworkBuilder::new().task("u", "unlock").task("e", "enter").sequence(["u", "e"]))
+            .domain()
+            .unwrap();
+        let network = NetworkBuilder::new().task("t", "t").build(&domain).unwrap();
+        let locked = HashSet::from(["locked".to_string()]);
+        let strategies: Vec<SearchStrategy<String>> = vec![
+            SearchStrategy::BreadthFirst,
+            SearchStrategy::AStar(Box::new(TdgHeuristic::from_domain(&domain))),
+            SearchStrategy::AStar(Box::new(RelaxedComposition::from_domain(&domain, RelaxedEstimate::FF))),
+        ];
+        for strategy in strategies {
+            let mut search = ProgressionSearch::with_strategy(strategy, TieBreaking::Fifo);
+            match search.run(locked.clone(), network.clone()).unwrap() {
+                SearchResult::Solved { plan, .. } => {
+                    assert_eq!(plan.action_names(), vec!["unlock", "enter"]);
+                    let verifier = PlanVerifier::new(&locked, &network);
+                    assert_eq!(verifier.verify_plan(&plan), Ok(()));
+                }
+                x => panic!("unexpected result {:?}", x),
+            }
+        }
+        let enter = NetworkBuilder::new().task("e", "enter").build(&domain).unwrap();
+        let result = ProgressionSearch::new().run(locked, enter);
+        assert!(matches!(result, Ok(SearchResult::Unsolvable)));
+    }
+
+    #[test]
+    pub fn conditional_effect_test() {
+        let fact = |x: &str| HashSet::from([x.to_string()]);
+        let alarm = ConditionalEffect::new(fact("armed"), fact("alarm"), HashSet::new());
+        let domain = DomainBuilder::<String>::new()
+            .primitive("open", [], ["open".to_string()], [])
+            .conditional_effect("open", alarm)
+            .primitive("call", [], [], [])
+            .compound("respond")
+            .method("respond", "m-call", NetworkBuilder::new().task("c", "call"))
+            .method_preconditions("respond", "m-call", ["alarm".to_string()])
+            .domain()
+            .unwrap();
+        let network = NetworkBuilder::new().task("o", "open").task("r", "respond").before("o", "r").build(&domain).unwrap();
+        let strategies = || -> Vec<SearchStrategy<String>> {
+            vec![
+                SearchStrategy::BreadthFirst,
+                SearchStrategy::AStar(Box::new(RelaxedComposition::from_domain(&domain, RelaxedEstimate::Max))),
+            ]
+        };
+        for strategy in strategies() {
+            let mut search = ProgressionSearch::with_strategy(strategy, TieBreaking::Fifo);
+            match search.run(fact("armed"), network.clone()).unwrap() {
+                SearchResult::Solved { plan, .. } => assert_eq!(plan.action_names(), vec!["open", "call"]),
+                x => panic!("unexpected result {:?}", x),
+            }
+            let result = search.run(HashSet::new(), network.clone());
+            assert!(matches!(result, Ok(SearchResult::Unsolvable)));
+        }
+    }
+}
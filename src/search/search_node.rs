@@ -1,19 +1,184 @@
-use std::{collections::HashSet, hash::Hash};
+use std::{collections::HashSet, hash::Hash, rc::Rc};
 use super::HTN;
+use super::{Applicability, HtnError, Method, PrimitiveAction};
+use crate::plan::{Decomposition, Plan, PlanAction};
 
 #[derive(Debug)]
+enum PlanStep {
+    Action(PlanAction),
+    Decomposition(Decomposition),
+}
+
+// A step of the plan linked to the steps before it, so that successors
+// share the plan of the node they were generated from
+#[derive(Debug)]
+struct PlanTrail {
+    step: PlanStep,
+    previous: Option<Rc<PlanTrail>>,
+}
+
+// Unlinks the steps one by one, as dropping a long trail recursively would
+// overflow the stack
+impl Drop for PlanTrail {
+    fn drop(&mut self) {
+        let mut previous = self.previous.take();
+        while let Some(trail) = previous {
+            match Rc::try_unwrap(trail) {
+                Ok(mut trail) => previous = trail.previous.take(),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct SearchNode<T: Hash + Eq> {
     pub state: HashSet<T>,
     pub network: HTN<T>,
-    pub sequence: Vec<String>
+    // The plan so far, whose instance ids are the ids of the network
+    root: Rc<[u32]>,
+    trail: Option<Rc<PlanTrail>>,
+    length: usize,
+    // Decomposition depth of the tasks in the network, and of the plan
+    depths: im_rc::HashMap<u32, usize>,
+    depth: usize,
+    /// Cost of the actions applied and methods used so far
+    pub cost: u32,
 }
 
 impl <T: Hash + Eq> SearchNode<T> {
-    pub fn new(state: HashSet<T>, network: HTN<T>, sequence: Vec<String>) -> SearchNode<T> {
-        SearchNode { state, network, sequence }
+    pub fn new(state: HashSet<T>, network: HTN<T>) -> SearchNode<T> {
+        let mut root: Vec<u32> = network.get_task_ids().into_iter().collect();
+        root.sort();
+        SearchNode {
+            state,
+            network,
+            root: root.into(),
+            trail: None,
+            length: 0,
+            depths: im_rc::HashMap::new(),
+            depth: 0,
+            cost: 0,
+        }
     }
 
     pub fn is_goal(&self) -> bool {
         self.network.count_tasks() == 0
     }
-}
\ No newline at end of file
+
+    /// The plan leading to this node.
+    pub fn plan(&self) -> Plan {
+        let mut actions = Vec::with_capacity(self.length);
+        let mut decompositions = Vec::new();
+        let mut trail = self.trail.as_deref();
+        while let Some(t) = trail {
+            match &t.step {
+                PlanStep::Action(a) => actions.push(a.clone()),
+                PlanStep::Decomposition(d) => decompositions.push(d.clone()),
+            }
+            trail = t.previous.as_deref();
+        }
+        actions.reverse();
+        decompositions.reverse();
+        Plan::new(actions, self.root.to_vec(), decompositions)
+    }
+
+    /// Number of actions in the plan, without building it.
+    pub fn plan_len(&self) -> usize {
+        self.length
+    }
+
+    /// `Plan::depth` of the plan, without building it.
+    pub fn plan_depth(&self) -> usize {
+        self.depth
+    }
+
+    fn extend(&self, step: PlanStep) -> Option<Rc<PlanTrail>> {
+        Some(Rc::new(PlanTrail {
+            step,
+            previous: self.trail.clone(),
+        }))
+    }
+
+    pub fn apply_action(&self, id: u32, action: &PrimitiveAction<T>) -> Result<SearchNode<T>, HtnError>
+    where T: Clone {
+        let network = self.network.apply_action(id)?;
+        let trail = self.extend(PlanStep::Action(PlanAction {
+            id,
+            name: action.name.clone(),
+        }));
+        let mut depths = self.depths.clone();
+        depths.remove(&id);
+        Ok(SearchNode {
+            state: action.transition(&self.state),
+            network,
+            root: Rc::clone(&self.root),
+            trail,
+            length: self.length + 1,
+            depths,
+            depth: self.depth,
+            cost: self.cost.saturating_add(action.cost()),
+        })
+    }
+
+    pub fn decompose(&self, id: u32, method: &Method<T>) -> Result<SearchNode<T>, HtnError>
+    where T: Clone {
+        let network = self.network.decompose(id, method)?;
+        // The subtasks received the ids allocated by the decomposition
+        let subtasks: Vec<u32> = (self.network.next_id()..network.next_id()).collect();
+        let mut depths = self.depths.clone();
+        let depth = depths.remove(&id).unwrap_or(0) + 1;
+        depths.extend(subtasks.iter().map(|x| (*x, depth)));
+        let trail = self.extend(PlanStep::Decomposition(Decomposition {
+            id,
+            task: self.network.get_task(id).unwrap().get_name(),
+            method: method.name.clone(),
+            subtasks,
+        }));
+        Ok(SearchNode {
+            state: self.state.clone(),
+            network,
+            root: Rc::clone(&self.root),
+            trail,
+            length: self.length,
+            depths,
+            depth: self.depth.max(depth),
+            cost: self.cost.saturating_add(method.cost),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::example::create_problem_instance;
+    use crate::task_network::Task;
+
+    #[test]
+    fn shared_plan_test() {
+        let root = SearchNode::new(HashSet::new(), create_problem_instance());
+        let mut node = root.clone();
+        let mut path = Vec::new();
+        while !node.is_goal() {
+            let id = *node.network.get_unconstrained_tasks().iter().min().unwrap();
+            let next = match node.network.get_task(id).unwrap() {
+                Task::Primitive(a) => node.apply_action(id, a).unwrap(),
+                Task::Compound(c) => node.decompose(id, &c.methods()[0]).unwrap(),
+            };
+            path.push(node);
+            node = next;
+        }
+        let plan = node.plan();
+        assert_eq!(plan.len(), node.plan_len());
+        assert_eq!(plan.depth(), node.plan_depth());
+        assert_eq!(plan.root, root.plan().root);
+        // Earlier nodes keep their own, shorter plans
+        for earlier in path.iter() {
+            let prefix = earlier.plan();
+            assert_eq!(prefix.len(), earlier.plan_len());
+            assert_eq!(prefix.actions[..], plan.actions[..prefix.len()]);
+            assert_eq!(prefix.decompositions[..], plan.decompositions[..prefix.decompositions.len()]);
+        }
+        assert_eq!(root.plan(), Plan::new(Vec::new(), plan.root.clone(), Vec::new()));
+    }
+}
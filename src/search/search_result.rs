@@ -1,5 +1,16 @@
-#[derive(Debug)]
+use serde::{Deserialize, Serialize};
+
+use crate::plan::Plan;
+
+use super::limits::Limit;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SearchResult {
+    /// The whole search space was explored without finding a solution
     Unsolvable,
-    Solved(Vec<String>)
-}
\ No newline at end of file
+    /// A plan together with its cost
+    Solved { plan: Plan, cost: u32 },
+    /// The search was stopped before it could decide the problem
+    LimitReached(Limit),
+}
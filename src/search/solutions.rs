@@ -0,0 +1,101 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::time::Instant;
+
+use crate::error::Error;
+use crate::plan::Plan;
+
+use super::limits::Limit;
+use super::progression_search::ProgressionSearch;
+use super::search_result::SearchResult;
+use super::statistics::SearchStatistics;
+
+/// Iterator over the solutions found by `ProgressionSearch::solutions`, in
+/// the order the search reaches them. It ends after the first error.
+pub struct Solutions<'a, T: Hash + Eq> {
+    search: &'a mut ProgressionSearch<T>,
+    start: Instant,
+    finished: bool,
+    limit: Option<Limit>,
+}
+
+impl<'a, T: Hash + Eq + Clone + Debug> Solutions<'a, T> {
+    pub(crate) fn new(search: &'a mut ProgressionSearch<T>, start: Instant) -> Solutions<'a, T> {
+        Solutions { search, start, finished: false, limit: None }
+    }
+
+    /// The limit that ended the enumeration, if it did not run to completion.
+    pub fn limit_reached(&self) -> Option<Limit> {
+        self.limit
+    }
+
+    pub fn statistics(&self) -> &SearchStatistics {
+        self.search.statistics()
+    }
+}
+
+impl<T: Hash + Eq + Clone + Debug> Iterator for Solutions<'_, T> {
+    type Item = Result<Plan, Error>;
+
+    fn next(&mut self) -> Option<Result<Plan, Error>> {
+        if self.finished {
+            return None;
+        }
+        match self.search.resume(self.start) {
+            Ok(SearchResult::Solved { plan, .. }) => Some(Ok(plan)),
+            Ok(SearchResult::Unsolvable) => {
+                self.finished = true;
+                None
+            }
+            Ok(SearchResult::LimitReached(limit)) => {
+                self.finished = true;
+                self.limit = Some(limit);
+                None
+            }
+            Err(e) => {
+                self.finished = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::example::create_problem_instance;
+    use crate::search::SearchLimits;
+    use std::collections::HashSet;
+
+    #[test]
+    fn all_solutions_test() {
+        let mut search = ProgressionSearch::new();
+        let plans: Vec<Plan> = search.solutions(HashSet::new(), create_problem_instance()).map(Result::unwrap).collect();
+        // ObtainPermit and HireBuilder as well as BuildRoof and BuildWalls commute
+        assert_eq!(plans.len(), 4);
+        let sequences: HashSet<Vec<String>> = plans.iter().map(|p| p.action_names()).collect();
+        assert_eq!(sequences.len(), 4);
+        assert!(plans.iter().all(|p| p.len() == 8));
+    }
+
+    #[test]
+    fn limited_solutions_test() {
+        let domain = "
+            (define (domain d) (:task spin)
+              (:method m-again :task (spin) :ordered-subtasks (and (a) (spin)))
+              (:method m-done :task (spin) :subtasks ())
+              (:action a))";
+        let problem = "(define (problem p) (:domain d) (:htn :subtasks (spin)))";
+        let (state, htn) = crate::hddl::load_problem(domain, problem).unwrap();
+        let mut search = ProgressionSearch::new();
+        let lengths: Vec<usize> = search.solutions(state.clone(), htn.clone()).take(3).map(|p| p.unwrap().len()).collect();
+        assert_eq!(lengths, vec![0, 1, 2]);
+
+        search.set_limits(SearchLimits { max_expansions: Some(20), ..SearchLimits::new() });
+        let mut solutions = search.solutions(state, htn);
+        assert!(solutions.by_ref().count() > 3);
+        assert_eq!(solutions.limit_reached(), Some(Limit::Expansions));
+        assert_eq!(solutions.statistics().expanded, 20);
+        assert!(solutions.next().is_none());
+    }
+}
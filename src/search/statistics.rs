@@ -0,0 +1,19 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// Counters collected during a single run of `ProgressionSearch`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SearchStatistics {
+    /// Nodes taken from the fringe and expanded
+    pub expanded: usize,
+    /// Successor nodes created, including those pruned afterwards
+    pub generated: usize,
+    pub decompositions: usize,
+    pub action_applications: usize,
+    pub peak_fringe_size: usize,
+    pub duplicates_pruned: usize,
+    /// Nodes the heuristic reported as dead ends
+    pub dead_ends_pruned: usize,
+    pub elapsed: Duration,
+}
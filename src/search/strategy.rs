@@ -0,0 +1,99 @@
+use std::hash::Hash;
+
+use super::heuristic::Heuristic;
+use super::search_node::SearchNode;
+
+/// The order in which `ProgressionSearch` expands its fringe. The informed
+/// strategies order nodes by `f = g + w * h`, where `g` is the cost of the
+/// actions and methods applied so far. Uniform-cost search, and A* with an
+/// admissible heuristic, return plans of minimal cost. The depth-first
+/// strategies only keep the current path and its pending siblings, and never
+/// revisit a (state, task network) pair already on the path.
+pub enum SearchStrategy<T: Hash + Eq> {
+    BreadthFirst,
+    /// Expands nodes in order of their cost
+    UniformCost,
+    DepthFirst(Option<DepthBound>),
+    /// Depth-first search with the bound raised from zero up to the given one
+    IterativeDeepening(DepthBound),
+    AStar(Box<dyn Heuristic<T>>),
+    WeightedAStar(Box<dyn Heuristic<T>>, f64),
+    GreedyBestFirst(Box<dyn Heuristic<T>>),
+}
+
+impl<T: Hash + Eq> SearchStrategy<T> {
+    pub fn heuristic(&self) -> Option<&dyn Heuristic<T>> {
+        match self {
+            SearchStrategy::BreadthFirst
+            | SearchStrategy::UniformCost
+            | SearchStrategy::DepthFirst(_)
+            | SearchStrategy::IterativeDeepening(_) => None,
+            SearchStrategy::AStar(h)
+            | SearchStrategy::WeightedAStar(h, _)
+            | SearchStrategy::GreedyBestFirst(h) => Some(h.as_ref()),
+        }
+    }
+
+    pub fn priority(&self, g: u32, h: u32) -> f64 {
+        match self {
+            SearchStrategy::BreadthFirst
+            | SearchStrategy::DepthFirst(_)
+            | SearchStrategy::IterativeDeepening(_) => 0.0,
+            SearchStrategy::UniformCost => g as f64,
+            SearchStrategy::AStar(_) => g as f64 + h as f64,
+            SearchStrategy::WeightedAStar(_, w) => g as f64 + w * h as f64,
+            SearchStrategy::GreedyBestFirst(_) => h as f64,
+        }
+    }
+
+    pub(crate) fn depth_bound(&self) -> Option<(Option<DepthBound>, bool)> {
+        match self {
+            SearchStrategy::DepthFirst(bound) => Some((*bound, false)),
+            SearchStrategy::IterativeDeepening(bound) => Some((Some(*bound), true)),
+            _ => None,
+        }
+    }
+}
+
+/// Bounds the paths explored by the depth-first strategies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepthBound {
+    /// Number of actions in the plan
+    PlanLength(usize),
+    /// Nesting depth of decompositions, see `Plan::depth`
+    DecompositionDepth(usize),
+}
+
+impl DepthBound {
+    pub fn limit(&self) -> usize {
+        match self {
+            DepthBound::PlanLength(x) | DepthBound::DecompositionDepth(x) => *x,
+        }
+    }
+
+    pub(crate) fn with_limit(&self, limit: usize) -> DepthBound {
+        match self {
+            DepthBound::PlanLength(_) => DepthBound::PlanLength(limit),
+            DepthBound::DecompositionDepth(_) => DepthBound::DecompositionDepth(limit),
+        }
+    }
+
+    pub(crate) fn measure<T: Hash + Eq>(&self, node: &SearchNode<T>) -> usize {
+        match self {
+            DepthBound::PlanLength(_) => node.plan_len(),
+            DepthBound::DecompositionDepth(_) => node.plan_depth(),
+        }
+    }
+}
+
+/// How nodes with the same priority are ordered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TieBreaking {
+    /// Oldest node first
+    #[default]
+    Fifo,
+    /// Newest node first
+    Lifo,
+    /// Lowest heuristic value first, then oldest
+    LowestHeuristic,
+}
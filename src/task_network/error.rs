@@ -0,0 +1,46 @@
+use std::error::Error;
+use std::fmt;
+
+/// A structural problem found by `HTN::try_new` or `HTN::validate`, or a
+/// task id that an operation on a network cannot be applied to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HtnError {
+    /// The id does not belong to a task of the network
+    UnknownTask(u32),
+    NotPrimitive(u32),
+    NotCompound(u32),
+    /// An ordering refers to a task id that is not in the network
+    DanglingOrdering { before: u32, after: u32 },
+    MissingMapping(u32),
+    /// A mapping is given for an id that is not in the network
+    UnknownMapping(u32),
+    /// The orderings contain a cycle through the given task
+    CyclicOrdering(u32),
+    CompoundWithoutMethods { id: u32, task: String },
+    /// The decomposition of a method is invalid
+    InvalidMethod { task: String, method: String, error: Box<HtnError> },
+}
+
+impl fmt::Display for HtnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HtnError::UnknownTask(id) => write!(f, "task {} is not in the network", id),
+            HtnError::NotPrimitive(id) => write!(f, "task {} is not primitive", id),
+            HtnError::NotCompound(id) => write!(f, "task {} is not compound", id),
+            HtnError::DanglingOrdering { before, after } => {
+                write!(f, "ordering {} < {} refers to a task that does not exist", before, after)
+            }
+            HtnError::MissingMapping(id) => write!(f, "task {} has no mapping", id),
+            HtnError::UnknownMapping(id) => write!(f, "mapping for {} does not belong to a task", id),
+            HtnError::CyclicOrdering(id) => write!(f, "the orderings contain a cycle through task {}", id),
+            HtnError::CompoundWithoutMethods { id, task } => {
+                write!(f, "compound task {} (`{}`) has no methods", id, task)
+            }
+            HtnError::InvalidMethod { task, method, error } => {
+                write!(f, "method `{}` of `{}`: {}", method, task, error)
+            }
+        }
+    }
+}
+
+impl Error for HtnError {}
@@ -1,50 +1,39 @@
-use std::{
-    collections::{HashMap, HashSet},
-    iter::repeat,
-};
+use std::collections::HashSet as StdHashSet;
 
+use im_rc::{HashMap, HashSet};
+
+/// A partial order over task ids. Nodes and edges are persistent maps, so
+/// the graphs derived from one another share every part left unchanged.
+/// Predecessors and the nodes without any are kept up to date on every
+/// change, so neither requires a scan over all edges.
 #[derive(Debug, Clone)]
 pub struct Graph {
     pub nodes: HashSet<u32>,
+    // Successors of each node, only for nodes that have any
     pub edges: HashMap<u32, HashSet<u32>>,
+    predecessors: HashMap<u32, HashSet<u32>>,
+    unconstrained: HashSet<u32>,
 }
 
 impl Graph {
-    pub fn new(nodes: HashSet<u32>, orderings: Vec<(u32, u32)>) -> Self {
-        let mut edges: HashMap<u32, HashSet<u32>> = HashMap::with_capacity(nodes.len());
-        for edge in orderings.into_iter() {
-            match edge {
-                (x, y) => match edges.get_mut(&x) {
-                    Some(val) => {
-                        val.insert(y);
-                    }
-                    None => {
-                        edges.insert(x, HashSet::from([y]));
-                    }
-                },
-            }
-        }
-        Graph {
+    pub fn new(nodes: StdHashSet<u32>, orderings: Vec<(u32, u32)>) -> Self {
+        let nodes: HashSet<u32> = nodes.into_iter().collect();
+        let mut graph = Graph {
+            unconstrained: nodes.clone(),
             nodes,
-            edges: edges,
+            edges: HashMap::new(),
+            predecessors: HashMap::new(),
+        };
+        for (x, y) in orderings.into_iter() {
+            graph.add_edge(x, y);
         }
+        graph
     }
 
     pub fn get_edges(&self) -> Vec<(u32, u32)> {
         self.edges
-            .clone()
-            .into_iter()
-            .map(|(k, v)| repeat(k).zip(v).collect::<Vec<_>>())
-            .flatten()
-            .collect()
-    }
-
-    pub fn convert_edges_to_vec(edges: &HashMap<u32, HashSet<u32>>) -> Vec<(u32, u32)> {
-        edges
-            .clone()
-            .into_iter()
-            .map(|(k, v)| repeat(k).zip(v).collect::<Vec<_>>())
-            .flatten()
+            .iter()
+            .flat_map(|(k, v)| v.iter().map(move |x| (*k, *x)))
             .collect()
     }
 
@@ -52,112 +41,126 @@ impl Graph {
         self.nodes.len()
     }
 
+    #[allow(dead_code)]
     pub fn get_neighbors(&self, id: u32) -> Option<&HashSet<u32>> {
         self.edges.get(&id)
     }
 
-    pub fn get_unconstrained_nodes(&self) -> HashSet<u32> {
-        let mut result = self.nodes.clone();
-        for k in self.edges.keys() {
-            for val in self.edges.get(k).unwrap() {
-                result.remove(val);
-            }
-        }
-        result
+    pub fn get_predecessors(&self, id: u32) -> Option<&HashSet<u32>> {
+        self.predecessors.get(&id)
     }
 
-    pub fn get_incoming_edges(&self, id: u32) -> HashSet<u32> {
-        HashSet::from_iter(
-            self.edges
-                .iter()
-                .filter(|(_, v)| v.contains(&id))
-                .map(|(k, _)| *k),
-        )
+    pub fn get_unconstrained_nodes(&self) -> StdHashSet<u32> {
+        self.unconstrained.iter().cloned().collect()
+    }
+
+    pub fn get_incoming_edges(&self, id: u32) -> StdHashSet<u32> {
+        match self.predecessors.get(&id) {
+            Some(predecessors) => predecessors.iter().cloned().collect(),
+            None => StdHashSet::new(),
+        }
     }
 
     pub fn remove_node(&self, id: u32) -> Graph {
         if !self.nodes.contains(&id) {
-            self.clone()
-        } else {
-            let mut new_nodes = self.nodes.clone();
-            new_nodes.remove(&id);
-            let mut new_edges = Vec::with_capacity(self.edges.keys().len());
-            for (key, values) in self.edges.iter() {
-                if *key == id {
-                    continue;
-                }
-                for value in values {
-                    if *value == id {
-                        continue;
-                    } else {
-                        new_edges.push((*key, *value))
-                    }
+            return self.clone();
+        }
+        let mut graph = self.clone();
+        graph.nodes.remove(&id);
+        graph.unconstrained.remove(&id);
+        if let Some(successors) = self.edges.get(&id) {
+            for successor in successors.iter() {
+                graph.remove_edge(id, *successor);
+            }
+        }
+        if let Some(predecessors) = self.predecessors.get(&id) {
+            for predecessor in predecessors.iter() {
+                graph.remove_edge(*predecessor, id);
+            }
+        }
+        graph
+    }
+
+    fn add_edge(&mut self, from: u32, to: u32) {
+        if self.edges.entry(from).or_default().insert(to).is_none() {
+            self.predecessors.entry(to).or_default().insert(from);
+            self.unconstrained.remove(&to);
+        }
+    }
+
+    fn remove_edge(&mut self, from: u32, to: u32) {
+        if let Some(successors) = self.edges.get_mut(&from) {
+            successors.remove(&to);
+            if successors.is_empty() {
+                self.edges.remove(&from);
+            }
+        }
+        if let Some(predecessors) = self.predecessors.get_mut(&to) {
+            predecessors.remove(&from);
+            if predecessors.is_empty() {
+                self.predecessors.remove(&to);
+                if self.nodes.contains(&to) {
+                    self.unconstrained.insert(to);
                 }
             }
-            Graph::new(new_nodes, new_edges)
+        }
+    }
+
+    fn add_edges(&mut self, from: u32, to: &HashSet<u32>) {
+        for x in to.iter() {
+            self.add_edge(from, *x);
         }
     }
 
     pub fn add_subgraph(
         &self,
         subgraph: Graph,
-        incoming_edges: HashSet<u32>,
-        outgoing_edges: HashSet<u32>,
+        incoming_edges: StdHashSet<u32>,
+        outgoing_edges: StdHashSet<u32>,
     ) -> Graph {
-        let nodes = self.nodes.clone().union(&subgraph.nodes).cloned().collect();
-        let mut orderings = self.edges.clone();
+        let outgoing_edges: HashSet<u32> = outgoing_edges.into_iter().collect();
+        let mut graph = self.clone();
+        for node in subgraph.nodes.iter() {
+            if graph.nodes.insert(*node).is_none() && !graph.predecessors.contains_key(node) {
+                graph.unconstrained.insert(*node);
+            }
+        }
+
+        // An empty subgraph must not drop the orderings that went through the
+        // replaced node
+        let unconstrained_nodes = if subgraph.nodes.is_empty() {
+            outgoing_edges.clone()
+        } else {
+            subgraph.unconstrained.clone()
+        };
 
         // Adding incoming edges
-        let unconstrained_nodes = subgraph.get_unconstrained_nodes();
         for node in incoming_edges.iter() {
-            match orderings.contains_key(node) {
-                false => orderings.insert(*node, unconstrained_nodes.clone()),
-                true => orderings.insert(
-                    *node,
-                    unconstrained_nodes
-                        .union(orderings.get(node).unwrap())
-                        .cloned()
-                        .collect(),
-                ),
-            };
+            graph.add_edges(*node, &unconstrained_nodes);
         }
 
         // Adding outgoing edges
-        let terminal_nodes: HashSet<u32> = subgraph
-            .nodes
-            .difference(&subgraph.edges.keys().cloned().collect())
-            .cloned()
-            .collect();
-        for node in terminal_nodes.iter() {
-            orderings.insert(*node, outgoing_edges.clone());
+        for node in subgraph.nodes.iter() {
+            if !subgraph.edges.contains_key(node) {
+                graph.add_edges(*node, &outgoing_edges);
+            }
         }
 
         for (key, value) in subgraph.edges {
-            orderings.insert(key, value);
+            graph.add_edges(key, &value);
         }
-
-        let orderings = orderings
-            .into_iter()
-            .map(|(k, v)| repeat(k).zip(v).collect::<Vec<_>>())
-            .flatten()
-            .collect();
-        Graph::new(nodes, orderings)
+        graph
     }
 
-    pub fn to_layers(&self) -> Vec<HashSet<u32>> {
-        let mut result: Vec<HashSet<u32>> = Vec::new();
+    pub fn to_layers(&self) -> Vec<StdHashSet<u32>> {
+        let mut result: Vec<StdHashSet<u32>> = Vec::new();
         let mut prev_layer = self.get_unconstrained_nodes();
         result.push(prev_layer.clone());
         loop {
-            let mut layer: HashSet<u32> = HashSet::new();
+            let mut layer: StdHashSet<u32> = StdHashSet::new();
             for node in prev_layer.iter() {
-                match self.edges.get(node) {
-                    Some(x) => {
-                        for outgoing in x.iter() {
-                            layer.insert(*outgoing);
-                        }
-                    }
-                    None => continue,
+                if let Some(x) = self.edges.get(node) {
+                    layer.extend(x.iter().cloned());
                 }
             }
             if layer.is_empty() {
@@ -173,6 +176,42 @@ impl Graph {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashSet;
+
+    fn successors(g: &Graph, id: u32) -> HashSet<u32> {
+        g.edges.get(&id).unwrap().iter().cloned().collect()
+    }
+
+    // Recomputes what the graph tracks incrementally
+    fn assert_consistent(g: &Graph) {
+        let targets: HashSet<u32> = g.get_edges().into_iter().map(|(_, y)| y).collect();
+        let unconstrained: HashSet<u32> = g.nodes.iter().filter(|x| !targets.contains(x)).cloned().collect();
+        assert_eq!(g.get_unconstrained_nodes(), unconstrained);
+        for node in g.nodes.iter() {
+            let incoming: HashSet<u32> = g.get_edges().into_iter().filter(|(_, y)| y == node).map(|(x, _)| x).collect();
+            assert_eq!(g.get_incoming_edges(*node), incoming);
+        }
+    }
+
+    #[test]
+    fn incremental_tracking_test() {
+        let g = Graph::new(HashSet::from([1, 2, 3, 4]), vec![(1, 3), (2, 3), (3, 4)]);
+        assert_consistent(&g);
+        let removed = g.remove_node(1);
+        assert_consistent(&removed);
+        assert_eq!(removed.get_unconstrained_nodes(), HashSet::from([2]));
+        let removed = removed.remove_node(2);
+        assert_eq!(removed.get_unconstrained_nodes(), HashSet::from([3]));
+        let subgraph = Graph::new(HashSet::from([5, 6, 7]), vec![(5, 7), (6, 7)]);
+        let result = g.remove_node(3).add_subgraph(subgraph, HashSet::from([1, 2]), HashSet::from([4]));
+        assert_consistent(&result);
+        assert_eq!(result.get_incoming_edges(4), HashSet::from([7]));
+        let result = result.remove_node(1).remove_node(2);
+        assert_eq!(result.get_unconstrained_nodes(), HashSet::from([5, 6]));
+        // The original graph is left untouched
+        assert_consistent(&g);
+        assert_eq!(g.get_unconstrained_nodes(), HashSet::from([1, 2]));
+    }
     #[test]
     fn instantiation() {
         let nodes: HashSet<u32> = HashSet::from([1, 2, 3, 4]);
@@ -227,15 +266,26 @@ mod tests {
         let result = g.add_subgraph(subgraph, HashSet::from([1, 2]), HashSet::from([4]));
 
         // inherited orderings
-        assert_eq!(*result.edges.get(&1).unwrap(), HashSet::from([5]));
-        assert_eq!(*result.edges.get(&2).unwrap(), HashSet::from([5]));
-        assert_eq!(*result.edges.get(&9).unwrap(), HashSet::from([4]));
+        assert_eq!(successors(&result, 1), HashSet::from([5]));
+        assert_eq!(successors(&result, 2), HashSet::from([5]));
+        assert_eq!(successors(&result, 9), HashSet::from([4]));
 
         //subgraph orderings
-        assert_eq!(*result.edges.get(&5).unwrap(), HashSet::from([6]));
-        assert_eq!(*result.edges.get(&6).unwrap(), HashSet::from([7, 8]));
-        assert_eq!(*result.edges.get(&7).unwrap(), HashSet::from([9]));
-        assert_eq!(*result.edges.get(&8).unwrap(), HashSet::from([9]));
+        assert_eq!(successors(&result, 5), HashSet::from([6]));
+        assert_eq!(successors(&result, 6), HashSet::from([7, 8]));
+        assert_eq!(successors(&result, 7), HashSet::from([9]));
+        assert_eq!(successors(&result, 8), HashSet::from([9]));
+    }
+
+    #[test]
+    fn add_empty_subgraph_test() {
+        let nodes: HashSet<u32> = HashSet::from([1, 2, 4]);
+        let g = Graph::new(nodes, Vec::new());
+        let subgraph = Graph::new(HashSet::new(), Vec::new());
+        let result = g.add_subgraph(subgraph, HashSet::from([1, 2]), HashSet::from([4]));
+        assert_eq!(successors(&result, 1), HashSet::from([4]));
+        assert_eq!(successors(&result, 2), HashSet::from([4]));
+        assert_eq!(result.get_unconstrained_nodes(), HashSet::from([1, 2]));
     }
 
     #[test]
@@ -0,0 +1,35 @@
+/// Hands out the ids of task instances. Ids are never reused, not even once
+/// their task has left the network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdAllocator {
+    next: u32,
+}
+
+impl IdAllocator {
+    /// An allocator whose ids all lie above the given ones.
+    pub fn after(ids: impl IntoIterator<Item = u32>) -> IdAllocator {
+        let next = ids.into_iter().max().map_or(0, |x| x + 1);
+        IdAllocator { next }
+    }
+
+    pub fn fresh(&mut self) -> u32 {
+        let id = self.next;
+        self.next += 1;
+        id
+    }
+
+    /// The id the next call to `fresh` returns.
+    pub fn peek(&self) -> u32 {
+        self.next
+    }
+}
+
+/// Where a task instance introduced by a decomposition comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    /// The instance of the compound task that was decomposed
+    pub parent: u32,
+    pub method: String,
+    /// The id of the subtask in the method's own network
+    pub local_id: u32,
+}
@@ -1,9 +1,13 @@
 mod applicability;
+mod error;
 mod graph;
+mod ids;
 mod network;
 mod task_structs;
 
 
 pub use network::HTN;
-pub use task_structs::{CompoundTask, Task, Method, PrimitiveAction};
-pub use applicability::Applicability;
\ No newline at end of file
+pub use task_structs::{CompoundTask, ConditionalEffect, Task, Method, MethodOwner, PrimitiveAction};
+pub use applicability::Applicability;
+pub use error::HtnError;
+pub use ids::{IdAllocator, Provenance};
@@ -1,13 +1,36 @@
+use super::error::HtnError;
 use super::graph::Graph;
-use super::task_structs::{CompoundTask, Method, PrimitiveAction, Task};
+use super::ids::{IdAllocator, Provenance};
+use super::task_structs::{Method, MethodOwner, Task};
 use std::collections::{HashMap, HashSet};
-use std::hash::Hash;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::rc::Rc;
 
+/// A task network. Its graph and task mappings are persistent, so the
+/// networks derived by `decompose` and `apply_action` share all unchanged
+/// parts with the network they were derived from. Every decomposition gives
+/// the new subtasks fresh ids and records their provenance.
 #[derive(Debug)]
 pub struct HTN<T: Hash + Eq> {
     network: Graph,
-    mappings: HashMap<u32, Rc<Task<T>>>,
+    mappings: im_rc::HashMap<u32, Rc<Task<T>>>,
+    ids: IdAllocator,
+    provenance: im_rc::HashMap<u32, Provenance>,
+    owner: Option<Rc<MethodOwner<T>>>,
+}
+
+// Tasks are shared, so cloning a network does not require `T: Clone`
+impl<T: Hash + Eq> Clone for HTN<T> {
+    fn clone(&self) -> Self {
+        HTN {
+            network: self.network.clone(),
+            mappings: self.mappings.clone(),
+            ids: self.ids,
+            provenance: self.provenance.clone(),
+            owner: self.owner.clone(),
+        }
+    }
 }
 
 impl<T: Hash + Eq> HTN<T> {
@@ -17,9 +40,133 @@ impl<T: Hash + Eq> HTN<T> {
         mappings: HashMap<u32, Rc<Task<T>>>,
     ) -> HTN<T> {
         HTN {
+            ids: IdAllocator::after(tasks.iter().cloned()),
             network: Graph::new(tasks, orderings),
-            mappings,
+            mappings: mappings.into_iter().collect(),
+            provenance: im_rc::HashMap::new(),
+            owner: None,
+        }
+    }
+
+    /// Keeps the methods of the owner's tasks for as long as this network
+    /// or a network derived from it exists.
+    pub fn with_owner(mut self, owner: Rc<MethodOwner<T>>) -> HTN<T> {
+        self.owner = Some(owner);
+        self
+    }
+
+    pub fn owner(&self) -> Option<&Rc<MethodOwner<T>>> {
+        self.owner.as_ref()
+    }
+
+    /// Like `new`, but checks the network and, recursively, the
+    /// decompositions of every method reachable from it.
+    pub fn try_new(
+        tasks: HashSet<u32>,
+        orderings: Vec<(u32, u32)>,
+        mappings: HashMap<u32, Rc<Task<T>>>,
+    ) -> Result<HTN<T>, HtnError> {
+        let htn = HTN::new(tasks, orderings, mappings);
+        htn.validate()?;
+        Ok(htn)
+    }
+
+    /// Checks that every task has a mapping, the orderings are acyclic and
+    /// every compound task has a method, both for this network and for the
+    /// decompositions of all methods reachable from it.
+    pub fn validate(&self) -> Result<(), HtnError> {
+        self.validate_network()?;
+        let mut visited = HashSet::new();
+        let mut stack: Vec<Rc<Task<T>>> = self.mappings.values().cloned().collect();
+        while let Some(task) = stack.pop() {
+            if let Task::Compound(c) = task.as_ref() {
+                // Recursive domains refer back to tasks already checked
+                if !visited.insert(c.name.clone()) {
+                    continue;
+                }
+                for method in c.methods().iter() {
+                    let network = &method.decomposition;
+                    network.validate_network().map_err(|e| HtnError::InvalidMethod {
+                        task: c.name.clone(),
+                        method: method.name.clone(),
+                        error: Box::new(e),
+                    })?;
+                    stack.extend(network.mappings.values().cloned());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Checks this network only, without the methods of its tasks
+    pub(crate) fn validate_network(&self) -> Result<(), HtnError> {
+        let mut ids: Vec<u32> = self.network.nodes.iter().cloned().collect();
+        ids.sort_unstable();
+        for (before, after) in self.network.get_edges() {
+            if !self.network.nodes.contains(&before) || !self.network.nodes.contains(&after) {
+                return Err(HtnError::DanglingOrdering { before, after });
+            }
+        }
+        if let Some(id) = ids.iter().find(|x| !self.mappings.contains_key(x)) {
+            return Err(HtnError::MissingMapping(*id));
+        }
+        if let Some(id) = self.mappings.keys().find(|x| !self.network.nodes.contains(x)) {
+            return Err(HtnError::UnknownMapping(*id));
+        }
+        for id in ids.iter() {
+            if let Task::Compound(c) = self.mappings[id].as_ref() {
+                if c.methods().is_empty() {
+                    return Err(HtnError::CompoundWithoutMethods { id: *id, task: c.name.clone() });
+                }
+            }
         }
+        // Kahn's algorithm: tasks never freed of predecessors lie on a cycle
+        // or after one
+        let mut in_degree: HashMap<u32, usize> = ids.iter().map(|x| (*x, 0)).collect();
+        for (_, after) in self.network.get_edges() {
+            *in_degree.get_mut(&after).unwrap() += 1;
+        }
+        let mut queue: Vec<u32> = self.network.get_unconstrained_nodes().into_iter().collect();
+        let mut visited = 0;
+        while let Some(id) = queue.pop() {
+            visited += 1;
+            if let Some(successors) = self.network.edges.get(&id) {
+                for successor in successors.iter() {
+                    let degree = in_degree.get_mut(successor).unwrap();
+                    *degree -= 1;
+                    if *degree == 0 {
+                        queue.push(*successor);
+                    }
+                }
+            }
+        }
+        if visited < ids.len() {
+            let on_cycle = self.cycle_member(&in_degree);
+            return Err(HtnError::CyclicOrdering(on_cycle));
+        }
+        Ok(())
+    }
+
+    // Walks backwards through tasks left with predecessors, which must
+    // eventually repeat a task on a cycle
+    fn cycle_member(&self, in_degree: &HashMap<u32, usize>) -> u32 {
+        let mut id = in_degree
+            .iter()
+            .filter(|(_, d)| **d > 0)
+            .map(|(x, _)| *x)
+            .min()
+            .unwrap();
+        let mut seen = HashSet::new();
+        while seen.insert(id) {
+            id = self
+                .network
+                .get_incoming_edges(id)
+                .into_iter()
+                .filter(|x| in_degree[x] > 0)
+                .min()
+                .unwrap();
+        }
+        id
     }
 
     pub fn count_tasks(&self) -> usize {
@@ -33,6 +180,19 @@ impl<T: Hash + Eq> HTN<T> {
         }
     }
 
+    // The tasks of the network, shared with the networks derived from it
+    pub(crate) fn mapped_tasks(&self) -> impl Iterator<Item = &Rc<Task<T>>> {
+        self.mappings.values()
+    }
+
+    pub(crate) fn mapping(&self, id: u32) -> Option<&Rc<Task<T>>> {
+        self.mappings.get(&id)
+    }
+
+    pub fn get_task_ids(&self) -> HashSet<u32> {
+        self.network.nodes.iter().cloned().collect()
+    }
+
     pub fn get_unconstrained_tasks(&self) -> HashSet<u32> {
         self.network.get_unconstrained_nodes()
     }
@@ -41,66 +201,80 @@ impl<T: Hash + Eq> HTN<T> {
         self.network.get_incoming_edges(id)
     }
 
-    pub fn decompose(&self, id: u32, method: &Method<T>) -> HTN<T> {
-        // TODO: Refactor this function
-        let mut subgraph_nodes = method.decomposition.network.nodes.clone();
-        let mut subgraph_edges = method.decomposition.network.edges.clone();
-        let mut subgraph_mappings = method.decomposition.mappings.clone();
-        if !subgraph_nodes.is_disjoint(&self.network.nodes) {
-            let intersection: HashSet<u32> =
-                method.decomposition.mappings.keys().cloned().collect();
-            let network_max_id = self.network.nodes.iter().fold(u32::MIN, |a, b| a.max(*b));
-            let max_id = subgraph_nodes.iter().fold(network_max_id, |a, b| a.max(*b));
-            let new_ids: HashMap<u32, u32> = intersection.into_iter().zip(max_id + 1..).collect();
-            for (prev_id, new_id) in new_ids.iter() {
-                let mapping_val = subgraph_mappings.remove(&prev_id).unwrap();
-                subgraph_mappings.insert(*new_id, mapping_val);
-                if subgraph_edges.contains_key(&prev_id) {
-                    let edges: HashSet<u32> = subgraph_edges.remove(&prev_id).unwrap();
-                    let mapped_edges = edges.into_iter().map(|x| {
-                        if new_ids.contains_key(&x) {
-                            *new_ids.get(&x).unwrap()
-                        } else {
-                            x
-                        }
-                    });
-
-                    subgraph_edges.insert(*new_id, mapped_edges.collect());
+    pub fn get_orderings(&self) -> Vec<(u32, u32)> {
+        self.network.get_edges()
+    }
+
+    // Every task of the network and of the methods reachable from it, by name
+    pub fn get_reachable_tasks(&self) -> HashMap<String, Rc<Task<T>>> {
+        let mut result: HashMap<String, Rc<Task<T>>> = HashMap::new();
+        let mut stack: Vec<Rc<Task<T>>> = self.mappings.values().cloned().collect();
+        while let Some(task) = stack.pop() {
+            let name = task.get_name();
+            if result.contains_key(&name) {
+                continue;
+            }
+            if let Task::Compound(c) = task.as_ref() {
+                for method in c.methods().iter() {
+                    stack.extend(method.decomposition.mappings.values().cloned());
                 }
-                subgraph_nodes.remove(&prev_id);
-                subgraph_nodes.insert(*new_id);
             }
+            result.insert(name, task);
         }
-        let mut new_graph = self.network.clone();
-        let outgoing_edges = self.network.edges.get(&id).unwrap().clone();
-        let incoming_edges = self.network.get_incoming_edges(id);
-
-        new_graph = new_graph.remove_node(id);
-        new_graph = new_graph.add_subgraph(
-            Graph::new(
-                subgraph_nodes.clone(),
-                Graph::convert_edges_to_vec(&subgraph_edges),
-            ),
-            incoming_edges,
-            outgoing_edges,
-        );
+        result
+    }
 
-        let mut new_mappings = self.mappings.clone();
-        new_mappings.remove(&id);
-        for (id, m) in subgraph_mappings {
-            new_mappings.insert(id, m);
+    pub fn decompose(&self, id: u32, method: &Method<T>) -> Result<HTN<T>, HtnError> {
+        if self.is_primitive(id)? {
+            return Err(HtnError::NotCompound(id));
+        }
+        let mut ids = self.ids;
+        let mut provenance = self.provenance.clone();
+        let mut local_ids: Vec<u32> = method.decomposition.network.nodes.iter().cloned().collect();
+        local_ids.sort_unstable();
+        let mut new_ids: HashMap<u32, u32> = HashMap::with_capacity(local_ids.len());
+        for local_id in local_ids {
+            let fresh = ids.fresh();
+            new_ids.insert(local_id, fresh);
+            provenance.insert(fresh, Provenance { parent: id, method: method.name.clone(), local_id });
         }
-        let new_nodes = self
+        let orderings = method
+            .decomposition
             .network
-            .nodes
-            .iter()
-            .filter(|x| **x != id)
-            .cloned()
-            .collect::<HashSet<u32>>()
-            .union(&subgraph_nodes)
-            .cloned()
+            .get_edges()
+            .into_iter()
+            .map(|(x, y)| (new_ids[&x], new_ids[&y]))
             .collect();
-        HTN::new(new_nodes, new_graph.get_edges(), new_mappings)
+        let subgraph = Graph::new(new_ids.values().cloned().collect(), orderings);
+        let subgraph_mappings = method
+            .decomposition
+            .mappings
+            .iter()
+            .filter_map(|(x, task)| Some((*new_ids.get(x)?, task.clone())));
+        let outgoing_edges = match self.network.edges.get(&id) {
+            Some(successors) => successors.iter().cloned().collect(),
+            None => HashSet::new(),
+        };
+        let incoming_edges = self.network.get_incoming_edges(id);
+        let network = self
+            .network
+            .remove_node(id)
+            .add_subgraph(subgraph, incoming_edges, outgoing_edges);
+        let mut mappings = self.mappings.clone();
+        mappings.remove(&id);
+        mappings.extend(subgraph_mappings);
+        Ok(HTN { network, mappings, ids, provenance, owner: self.owner.clone() })
+    }
+
+    /// The decomposition that introduced the task instance, or `None` for
+    /// the tasks the network was created with.
+    pub fn provenance(&self, id: u32) -> Option<&Provenance> {
+        self.provenance.get(&id)
+    }
+
+    /// The id the next decomposition assigns to its first subtask.
+    pub fn next_id(&self) -> u32 {
+        self.ids.peek()
     }
 
     pub fn is_isomorphic(tn1: &HTN<T>, tn2: &HTN<T>) -> bool {
@@ -112,20 +286,28 @@ impl<T: Hash + Eq> HTN<T> {
         let tasks_1 = tn1.layers_to_tasks(layers_1);
         let tasks_2 = tn2.layers_to_tasks(layers_2);
 
-        for (x, y) in tasks_1.into_iter().zip(tasks_2.into_iter()) {
+        for (x, y) in tasks_1.into_iter().zip(tasks_2) {
             if x != y {
                 return false;
             }
         }
 
-        return true;
+        true
     }
 
-    pub fn apply_action(&self, id: u32) -> HTN<T> {
-        let mut new_mapping = self.mappings.clone();
-        new_mapping.remove(&id);
-        let new_graph = self.network.remove_node(id);
-        HTN { network: new_graph, mappings: new_mapping }
+    pub fn apply_action(&self, id: u32) -> Result<HTN<T>, HtnError> {
+        if !self.is_primitive(id)? {
+            return Err(HtnError::NotPrimitive(id));
+        }
+        let mut mappings = self.mappings.clone();
+        mappings.remove(&id);
+        Ok(HTN {
+            network: self.network.remove_node(id),
+            mappings,
+            ids: self.ids,
+            provenance: self.provenance.clone(),
+            owner: self.owner.clone(),
+        })
     }
 
     fn layers_to_tasks(&self, layers: Vec<HashSet<u32>>) -> Vec<HashSet<&Task<T>>> {
@@ -137,11 +319,152 @@ impl<T: Hash + Eq> HTN<T> {
         result
     }
 
-    pub fn is_primitive(&self, id: u32) -> bool {
-        let task = self.mappings.get(&id).unwrap();
-        match task.as_ref() {
-            Task::Primitive(_) => true,
-            _ => false
+    /// Hashes the network independently of its task ids. Every task is
+    /// hashed together with the tasks before and after it, so networks that
+    /// only differ in their ids hash to the same value. Distinct networks may
+    /// still collide; `is_equivalent` tells them apart.
+    pub fn canonical_hash(&self) -> u64 {
+        let hashes = self.task_hashes();
+        let mut hashes: Vec<u64> = self.mappings.keys().filter_map(|id| hashes.get(id).copied()).collect();
+        hashes.sort_unstable();
+        let mut hasher = DefaultHasher::new();
+        hashes.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Whether the networks are the same up to task ids: their ids can be
+    /// matched one to one so that the tasks and orderings agree.
+    pub fn is_equivalent(&self, other: &HTN<T>) -> bool {
+        if self.count_tasks() != other.count_tasks() || self.get_orderings().len() != other.get_orderings().len() {
+            return false;
+        }
+        let hashes = self.task_hashes();
+        let mut candidates: HashMap<u64, Vec<u32>> = HashMap::new();
+        for (id, hash) in other.task_hashes() {
+            candidates.entry(hash).or_default().push(id);
+        }
+        let mut order: Vec<u32> = self.network.nodes.iter().copied().collect();
+        order.sort_unstable_by_key(|id| (candidates.get(&hashes[id]).map_or(0, |c| c.len()), *id));
+        if order.iter().any(|id| !candidates.contains_key(&hashes[id])) {
+            return false;
+        }
+        // Backtracking over the candidates of each task in turn, without
+        // recursion so that long networks are fine
+        let mut assignment: HashMap<u32, u32> = HashMap::new();
+        let mut used: HashSet<u32> = HashSet::new();
+        let mut next = vec![0; order.len()];
+        let mut i = 0;
+        while i < order.len() {
+            let x = order[i];
+            let options = &candidates[&hashes[&x]];
+            let mut found = false;
+            while next[i] < options.len() {
+                let y = options[next[i]];
+                next[i] += 1;
+                if !used.contains(&y) && self.agrees(other, x, y, &assignment, &used) {
+                    assignment.insert(x, y);
+                    used.insert(y);
+                    found = true;
+                    break;
+                }
+            }
+            if found {
+                i += 1;
+                continue;
+            }
+            next[i] = 0;
+            if i == 0 {
+                return false;
+            }
+            i -= 1;
+            let y = assignment.remove(&order[i]).unwrap();
+            used.remove(&y);
+        }
+        true
+    }
+
+    // Whether `x` can be matched with `other`'s `y`, given the tasks matched
+    // so far
+    fn agrees(&self, other: &HTN<T>, x: u32, y: u32, assignment: &HashMap<u32, u32>, used: &HashSet<u32>) -> bool {
+        if self.get_task(x) != other.get_task(y) {
+            return false;
+        }
+        let neighbours = [
+            (self.network.get_neighbors(x), other.network.get_neighbors(y)),
+            (self.network.get_predecessors(x), other.network.get_predecessors(y)),
+        ];
+        for (mine, theirs) in neighbours {
+            let mut matched = 0;
+            for z in mine.into_iter().flatten() {
+                if let Some(w) = assignment.get(z) {
+                    if !theirs.is_some_and(|t| t.contains(w)) {
+                        return false;
+                    }
+                    matched += 1;
+                }
+            }
+            if theirs.into_iter().flatten().filter(|w| used.contains(w)).count() != matched {
+                return false;
+            }
+        }
+        true
+    }
+
+    // Hashes every task with its name and those of the tasks before and
+    // after it. Tasks are visited in topological order, so that no chain is
+    // followed recursively.
+    fn task_hashes(&self) -> HashMap<u32, u64> {
+        let mut predecessors: HashMap<u32, usize> = self.network.nodes.iter().map(|x| (*x, 0)).collect();
+        for successors in self.network.edges.values() {
+            for y in successors {
+                *predecessors.entry(*y).or_default() += 1;
+            }
+        }
+        let mut open: Vec<u32> = predecessors.iter().filter(|(_, n)| **n == 0).map(|(x, _)| *x).collect();
+        let mut order = Vec::with_capacity(predecessors.len());
+        while let Some(x) = open.pop() {
+            order.push(x);
+            for y in self.network.edges.get(&x).into_iter().flatten() {
+                let n = predecessors.get_mut(y).unwrap();
+                *n -= 1;
+                if *n == 0 {
+                    open.push(*y);
+                }
+            }
+        }
+        let hash = |id: u32, neighbours: Option<&im_rc::HashSet<u32>>, done: &HashMap<u32, u64>| {
+            let mut hashes: Vec<u64> = neighbours.into_iter().flatten().filter_map(|x| done.get(x).copied()).collect();
+            hashes.sort_unstable();
+            let mut hasher = DefaultHasher::new();
+            self.get_task(id).map(|x| x.get_name()).hash(&mut hasher);
+            hashes.hash(&mut hasher);
+            hasher.finish()
+        };
+        let mut before = HashMap::with_capacity(order.len());
+        for id in order.iter() {
+            before.insert(*id, hash(*id, self.network.get_predecessors(*id), &before));
+        }
+        let mut after = HashMap::with_capacity(order.len());
+        for id in order.iter().rev() {
+            after.insert(*id, hash(*id, self.network.get_neighbors(*id), &after));
+        }
+        order
+            .into_iter()
+            .map(|id| {
+                let mut hasher = DefaultHasher::new();
+                (before[&id], after[&id]).hash(&mut hasher);
+                (id, hasher.finish())
+            })
+            .collect()
+    }
+
+    pub fn is_primitive(&self, id: u32) -> Result<bool, HtnError> {
+        if !self.network.nodes.contains(&id) {
+            return Err(HtnError::UnknownTask(id));
+        }
+        match self.mappings.get(&id) {
+            Some(task) => Ok(matches!(task.as_ref(), Task::Primitive(_))),
+            None => Err(HtnError::MissingMapping(id)),
         }
     }
 }
@@ -149,8 +472,10 @@ impl<T: Hash + Eq> HTN<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::task_network::{CompoundTask, PrimitiveAction};
 
-    fn create_initial_tasks<'a>() -> (Rc<Task<u32>>, Rc<Task<u32>>, Rc<Task<u32>>, Rc<Task<u32>>) {
+    #[allow(clippy::type_complexity)]
+    fn create_initial_tasks() -> (Rc<Task<u32>>, Rc<Task<u32>>, Rc<Task<u32>>, Rc<Task<u32>>) {
         let empty = HashSet::new();
         let t1 = Task::Primitive(PrimitiveAction::new(
             "ObtainPermit".to_string(),
@@ -191,7 +516,8 @@ mod tests {
         assert_eq!(network.get_task(5), None);
     }
 
-    fn decomposition_tasks<'a>() -> (
+    #[allow(clippy::type_complexity)]
+    fn decomposition_tasks() -> (
         Task<u32>,
         Task<u32>,
         Task<u32>,
@@ -262,14 +588,109 @@ mod tests {
         let alpha = HashMap::from([(1, t1), (2, t2), (3, t3), (4, t4)]);
         let orderings: Vec<(u32, u32)> = Vec::from([(1, 3), (2, 3), (3, 4)]);
         let network = HTN::new(t, orderings, alpha);
-        let result = network.decompose(3, &t3_method);
+        let result = network.decompose(3, &t3_method).unwrap();
         assert_eq!(result.count_tasks(), 8);
         assert_eq!(result.get_unconstrained_tasks(), HashSet::from([1, 2]));
-        assert_eq!(Graph::convert_edges_to_vec(&result.network.edges).len(), 8);
+        assert_eq!(result.network.get_edges().len(), 8);
         assert_eq!(result.get_task(3), None);
         assert_eq!(result.network.edges.get(&1).unwrap().len(), 1);
     }
 
+    #[test]
+    fn provenance_test() {
+        let (t1, t2, t3, t4) = create_initial_tasks();
+        let (t5, t6, t7, t8, t9) = decomposition_tasks();
+        let method = Method::new(
+            "method-01".to_string(),
+            HTN::new(
+                HashSet::from([1, 2, 3, 4, 5]),
+                Vec::from([(1, 2), (2, 3), (2, 4), (3, 5), (4, 5)]),
+                HashMap::from(
+                    [(1, Rc::new(t5)), (2, Rc::new(t6)), (3, Rc::new(t7)), (4, Rc::new(t8)), (5, Rc::new(t9))]
+                ),
+            ),
+        );
+        let alpha = HashMap::from([(1, t1), (2, t2), (3, t3), (4, t4)]);
+        let network = HTN::new(HashSet::from([1, 2, 3, 4]), vec![(1, 3), (2, 3), (3, 4)], alpha);
+        assert_eq!(network.next_id(), 5);
+        let result = network.decompose(3, &method).unwrap();
+        assert_eq!(result.get_task_ids(), HashSet::from([1, 2, 4, 5, 6, 7, 8, 9]));
+        for (id, local_id) in (5..10).zip(1..6) {
+            let expected = Provenance { parent: 3, method: "method-01".to_string(), local_id };
+            assert_eq!(result.provenance(id), Some(&expected));
+        }
+        assert_eq!(result.provenance(1), None);
+        // Ids of tasks that left the network are not handed out again
+        let result = result.apply_action(1).unwrap().apply_action(2).unwrap();
+        assert_eq!(result.next_id(), 10);
+        assert_eq!(network.next_id(), 5);
+    }
+
+    #[test]
+    fn decompose_last_task_test() {
+        let (t1, t2, _, _) = create_initial_tasks();
+        let (t5, ..) = decomposition_tasks();
+        let method = Method::new(
+            "method-01".to_string(),
+            HTN::new(HashSet::from([1]), Vec::new(), HashMap::from([(1, Rc::new(t5))])),
+        );
+        let t3 = Rc::new(Task::Compound(CompoundTask::new("Construct".to_string(), Vec::new())));
+        let network = HTN::new(
+            HashSet::from([1, 2, 3]),
+            Vec::from([(1, 3), (2, 3)]),
+            HashMap::from([(1, t1), (2, t2), (3, t3)]),
+        );
+        let result = network.decompose(3, &method).unwrap();
+        assert_eq!(result.count_tasks(), 3);
+        assert_eq!(result.get_unconstrained_tasks(), HashSet::from([1, 2]));
+        // The subtask takes the place of the task, after its predecessors
+        let mut orderings = result.get_orderings();
+        orderings.sort_unstable();
+        assert_eq!(orderings, vec![(1, 4), (2, 4)]);
+        let result = result.apply_action(1).unwrap().apply_action(2).unwrap();
+        assert_eq!(result.get_unconstrained_tasks(), HashSet::from([4]));
+    }
+
+    #[test]
+    fn decompose_empty_method_test() {
+        let (t1, t2, t3, t4) = create_initial_tasks();
+        let method = Method::new("method-01".to_string(), HTN::new(HashSet::new(), Vec::new(), HashMap::new()));
+        let alpha = HashMap::from([(1, t1), (2, t2), (3, t3), (4, t4)]);
+        let network = HTN::new(HashSet::from([1, 2, 3, 4]), vec![(1, 3), (2, 3), (3, 4)], alpha);
+        let result = network.decompose(3, &method).unwrap();
+        assert_eq!(result.get_task_ids(), HashSet::from([1, 2, 4]));
+        // The orderings through the decomposed task are kept
+        let mut orderings = result.get_orderings();
+        orderings.sort_unstable();
+        assert_eq!(orderings, vec![(1, 4), (2, 4)]);
+        assert_eq!(result.get_unconstrained_tasks(), HashSet::from([1, 2]));
+    }
+
+    #[test]
+    fn reachable_tasks_test() {
+        let (t1, t2, t3, t4) = create_initial_tasks();
+        let (t5, t6, ..) = decomposition_tasks();
+        if let Task::Compound(c) = t3.as_ref() {
+            c.add_method(Method::new(
+                "method-01".to_string(),
+                HTN::new(
+                    HashSet::from([1, 2]),
+                    Vec::from([(1, 2)]),
+                    HashMap::from([(1, Rc::new(t5)), (2, Rc::new(t6))]),
+                ),
+            ));
+        }
+        let network = HTN::new(
+            HashSet::from([1, 2, 3, 4]),
+            Vec::from([(1, 3), (2, 3), (3, 4)]),
+            HashMap::from([(1, t1), (2, t2), (3, t3), (4, t4)]),
+        );
+        let tasks = network.get_reachable_tasks();
+        assert_eq!(tasks.len(), 6);
+        assert!(tasks.contains_key("BuildFrame"));
+        assert_eq!(network.get_orderings().len(), 3);
+    }
+
     #[test]
     pub fn isomorphism_test() {
         let (t1, t2, t3, t4) = create_initial_tasks();
@@ -295,7 +716,50 @@ mod tests {
         );
 
         let result = HTN::is_isomorphic(&htn1, &htn2);
-        assert_eq!(result, true);
+        assert!(result);
+        assert_eq!(htn1.canonical_hash(), htn2.canonical_hash());
+        let htn3 = HTN::new(
+            HashSet::from([5, 6, 7, 8]),
+            Vec::from([(5, 7), (7, 8)]),
+            htn2.mappings.clone().into_iter().collect(),
+        );
+        assert_ne!(htn1.canonical_hash(), htn3.canonical_hash());
+    }
+
+    #[test]
+    fn long_chain_hash_test() {
+        // Deep enough to overflow the stack if the chain were followed recursively
+        let (t1, ..) = create_initial_tasks();
+        let n = 20_000;
+        let ids: HashSet<u32> = (1..=n).collect();
+        let orderings = (1..n).map(|x| (x, x + 1)).collect();
+        let mappings = (1..=n).map(|x| (x, Rc::clone(&t1))).collect();
+        let htn = HTN::new(ids, orderings, mappings);
+        let shifted = HTN::new(
+            (2..=n + 1).collect(),
+            (2..n + 1).map(|x| (x, x + 1)).collect(),
+            (2..=n + 1).map(|x| (x, Rc::clone(&t1))).collect(),
+        );
+        assert_eq!(htn.canonical_hash(), shifted.canonical_hash());
+        assert_ne!(htn.canonical_hash(), htn.apply_action(1).unwrap().canonical_hash());
+        assert!(htn.is_equivalent(&shifted));
+        assert!(!htn.is_equivalent(&htn.apply_action(1).unwrap()));
+    }
+
+    #[test]
+    fn equivalence_test() {
+        let (t1, t2, t3, _) = create_initial_tasks();
+        let mappings = HashMap::from([(1, t1), (2, t2), (3, Rc::clone(&t3)), (4, t3)]);
+        let ids = HashSet::from([1, 2, 3, 4]);
+        // Both tasks before one of the copies, or each before its own copy
+        let joined = HTN::new(ids.clone(), Vec::from([(1, 3), (2, 3)]), mappings.clone());
+        let split = HTN::new(ids.clone(), Vec::from([(1, 3), (2, 4)]), mappings.clone());
+        assert_ne!(joined.canonical_hash(), split.canonical_hash());
+        assert!(!joined.is_equivalent(&split));
+        assert!(!split.is_equivalent(&joined));
+        let renamed = HTN::new(ids, Vec::from([(1, 4), (2, 4)]), mappings);
+        assert_eq!(joined.canonical_hash(), renamed.canonical_hash());
+        assert!(joined.is_equivalent(&renamed));
     }
 
     #[test]
@@ -311,10 +775,10 @@ mod tests {
             orderings1,
             alpha,
         );
-        assert_eq!(htn.is_primitive(1), true);
-        assert_eq!(htn.is_primitive(2), true);
-        assert_eq!(htn.is_primitive(3), false);
-        assert_eq!(htn.is_primitive(4), true);
+        assert!(htn.is_primitive(1).unwrap());
+        assert!(htn.is_primitive(2).unwrap());
+        assert!(!htn.is_primitive(3).unwrap());
+        assert!(htn.is_primitive(4).unwrap());
     }
 
     #[test]
@@ -330,21 +794,21 @@ mod tests {
             orderings1,
             alpha,
         );
-        let new_htn = htn.apply_action(2);
+        let new_htn = htn.apply_action(2).unwrap();
         assert_eq!(new_htn.count_tasks(), 3);
         assert_eq!(new_htn.get_task(2), None);
-        assert_eq!(new_htn.is_primitive(3), false);
-        assert_eq!(new_htn.mappings.contains_key(&2), false);
-        let new_htn_2 = new_htn.apply_action(1);
+        assert!(!new_htn.is_primitive(3).unwrap());
+        assert!(!new_htn.mappings.contains_key(&2));
+        let new_htn_2 = new_htn.apply_action(1).unwrap();
         assert_eq!(new_htn_2.count_tasks(), 2);
         assert_eq!(new_htn_2.get_task(1), None);
-        assert_eq!(new_htn_2.is_primitive(3), false);
-        assert_eq!(new_htn_2.mappings.contains_key(&1), false);
+        assert!(!new_htn_2.is_primitive(3).unwrap());
+        assert!(!new_htn_2.mappings.contains_key(&1));
     }
 
     #[test]
     pub fn last_action_test() {
-        let (t1, t2, t3, t4) = create_initial_tasks();
+        let (t1, t2, _, t4) = create_initial_tasks();
         // first graph
         let nodes1: HashSet<u32> = HashSet::from([1, 2, 4]);
         let orderings1: Vec<(u32, u32)> = Vec::from([(1, 4), (2, 4)]);
@@ -355,9 +819,82 @@ mod tests {
             orderings1,
             alpha,
         );
-        let new_htn = htn.apply_action(2);
-        let new_htn_2 = new_htn.apply_action(1);
-        let new_htn_3 = new_htn_2.apply_action(4);
+        let new_htn = htn.apply_action(2).unwrap();
+        let new_htn_2 = new_htn.apply_action(1).unwrap();
+        let new_htn_3 = new_htn_2.apply_action(4).unwrap();
         assert_eq!(new_htn_3.count_tasks(), 0);
     }
+
+    #[test]
+    fn validation_test() {
+        let (t1, t2, t3, t4) = create_initial_tasks();
+        let mappings = HashMap::from([(1, t1), (2, t2), (3, t3), (4, t4.clone())]);
+        let ids = HashSet::from([1, 2, 3, 4]);
+        let result = HTN::try_new(ids.clone(), vec![(1, 3), (3, 5)], mappings.clone());
+        assert_eq!(result.err(), Some(HtnError::DanglingOrdering { before: 3, after: 5 }));
+        let result = HTN::try_new(HashSet::from([1, 2, 3, 4, 5]), vec![], mappings.clone());
+        assert_eq!(result.err(), Some(HtnError::MissingMapping(5)));
+        let result = HTN::try_new(HashSet::from([1, 2, 3]), vec![], mappings.clone());
+        assert_eq!(result.err(), Some(HtnError::UnknownMapping(4)));
+        let result = HTN::try_new(ids.clone(), vec![(1, 3), (2, 3), (3, 4)], mappings.clone());
+        let expected = HtnError::CompoundWithoutMethods { id: 3, task: "Construct".to_string() };
+        assert_eq!(result.err(), Some(expected));
+
+        let mut mappings = mappings;
+        mappings.insert(3, t4);
+        let result = HTN::try_new(ids.clone(), vec![(1, 2), (2, 3), (3, 1), (3, 4)], mappings.clone());
+        assert!(matches!(result.err(), Some(HtnError::CyclicOrdering(1..=3))));
+        assert!(HTN::try_new(ids, vec![(1, 2), (2, 3), (3, 4)], mappings).is_ok());
+    }
+
+    #[test]
+    fn method_validation_test() {
+        let action: Rc<Task<u32>> = Rc::new(Task::Primitive(PrimitiveAction::new(
+            "Act".to_string(),
+            HashSet::new(),
+            HashSet::new(),
+            HashSet::new(),
+        )));
+        // A recursive task whose second method has a cyclic decomposition
+        let task = Rc::new(Task::Compound(CompoundTask::new("Loop".to_string(), Vec::new())));
+        if let Task::Compound(c) = task.as_ref() {
+            let recursion = HTN::new(
+                HashSet::from([1, 2]),
+                vec![(1, 2)],
+                HashMap::from([(1, action.clone()), (2, task.clone())]),
+            );
+            c.add_method(Method::new("again".to_string(), recursion));
+        }
+        let htn = HTN::try_new(HashSet::from([1]), vec![], HashMap::from([(1, task.clone())]));
+        assert!(htn.is_ok());
+        if let Task::Compound(c) = task.as_ref() {
+            let cyclic = HTN::new(
+                HashSet::from([1, 2]),
+                vec![(1, 2), (2, 1)],
+                HashMap::from([(1, action.clone()), (2, action)]),
+            );
+            c.add_method(Method::new("broken".to_string(), cyclic));
+        }
+        match htn.unwrap().validate() {
+            Err(HtnError::InvalidMethod { task, method, error }) => {
+                assert_eq!((task.as_str(), method.as_str()), ("Loop", "broken"));
+                assert!(matches!(*error, HtnError::CyclicOrdering(_)));
+            }
+            x => panic!("unexpected result {:?}", x),
+        }
+    }
+
+    #[test]
+    fn misuse_test() {
+        let (t1, t2, t3, t4) = create_initial_tasks();
+        let mappings = HashMap::from([(1, t1), (2, t2), (3, t3), (4, t4)]);
+        let htn = HTN::new(HashSet::from([1, 2, 3, 4, 5]), vec![(1, 3)], mappings);
+        let method = Method::new("m".to_string(), HTN::new(HashSet::new(), vec![], HashMap::new()));
+        assert_eq!(htn.is_primitive(9), Err(HtnError::UnknownTask(9)));
+        assert_eq!(htn.is_primitive(5), Err(HtnError::MissingMapping(5)));
+        assert_eq!(htn.apply_action(3).err(), Some(HtnError::NotPrimitive(3)));
+        assert_eq!(htn.apply_action(9).err(), Some(HtnError::UnknownTask(9)));
+        assert_eq!(htn.decompose(1, &method).err(), Some(HtnError::NotCompound(1)));
+        assert!(htn.decompose(3, &method).is_ok());
+    }
 }
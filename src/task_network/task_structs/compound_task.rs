@@ -1,19 +1,128 @@
+use std::cell::{Ref, RefCell};
+use std::fmt;
 use std::hash::Hash;
+use std::mem;
+use std::rc::Rc;
 
-use super::Method;
+use super::{Method, Task};
 
-#[derive(Debug)]
 pub struct CompoundTask<T: Hash + Eq> {
     pub name: String,
-    pub methods: Vec<Method<T>>,
+    // Methods may refer back to the task that owns them (recursive domains),
+    // so they can be attached after the task has been shared through an `Rc`.
+    methods: RefCell<Vec<Method<T>>>,
 }
 
 impl<T: Hash + Eq> CompoundTask<T> {
     pub fn new(name: String, methods: Vec<Method<T>>) -> Self {
-        CompoundTask { name, methods }
+        CompoundTask {
+            name,
+            methods: RefCell::new(methods),
+        }
     }
 
-    pub fn add_method(&mut self, method: Method<T>) {
-        self.methods.push(method);
+    /// The methods of the task; the borrow must end before `add_method` is
+    /// called on the same task.
+    pub fn methods(&self) -> Ref<'_, Vec<Method<T>>> {
+        self.methods.borrow()
+    }
+
+    /// Adds a method through a shared reference, so that the task can be
+    /// among its own subtasks. Such reference cycles are only freed once the
+    /// task is held by a `MethodOwner`.
+    pub fn add_method(&self, method: Method<T>) {
+        self.methods.borrow_mut().push(method);
+    }
+}
+
+// Printing the methods' decompositions would recurse forever on recursive
+// domains, so only their names are shown.
+impl<T: Hash + Eq> fmt::Debug for CompoundTask<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let methods: Vec<String> = self.methods().iter().map(|m| m.name.clone()).collect();
+        f.debug_struct("CompoundTask")
+            .field("name", &self.name)
+            .field("methods", &methods)
+            .finish()
+    }
+}
+
+/// Holds compound tasks whose methods may refer back to them, as in
+/// recursive domains, and clears their methods once dropped so that the
+/// reference cycles are freed. Networks and domains built from such tasks
+/// share one, so the methods live as long as the last of them.
+pub struct MethodOwner<T: Hash + Eq> {
+    tasks: Vec<Rc<Task<T>>>,
+}
+
+impl<T: Hash + Eq> MethodOwner<T> {
+    pub fn new(tasks: impl IntoIterator<Item = Rc<Task<T>>>) -> MethodOwner<T> {
+        let tasks = tasks.into_iter().filter(|t| matches!(t.as_ref(), Task::Compound(_))).collect();
+        MethodOwner { tasks }
+    }
+}
+
+impl<T: Hash + Eq> Drop for MethodOwner<T> {
+    fn drop(&mut self) {
+        for task in self.tasks.iter() {
+            if let Task::Compound(c) = task.as_ref() {
+                // Dropped outside of the borrow, as it may release other tasks
+                let methods = mem::take(&mut *c.methods.borrow_mut());
+                drop(methods);
+            }
+        }
+    }
+}
+
+impl<T: Hash + Eq> fmt::Debug for MethodOwner<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tasks: Vec<String> = self.tasks.iter().map(|t| t.get_name()).collect();
+        f.debug_struct("MethodOwner").field("tasks", &tasks).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::task_network::{Task, HTN};
+
+    #[test]
+    fn recursive_method_test() {
+        let task: Rc<Task<u32>> = Rc::new(Task::Compound(CompoundTask::new("loop".to_string(), Vec::new())));
+        let network = HTN::new(HashSet::from([1]), Vec::new(), HashMap::from([(1, Rc::clone(&task))]));
+        let Task::Compound(c) = task.as_ref() else {
+            panic!("`loop` should be compound");
+        };
+        c.add_method(Method::new("m-again".to_string(), network));
+        let methods = c.methods();
+        assert_eq!(methods.len(), 1);
+        assert_eq!(methods[0].decomposition.get_task(1), Some(task.as_ref()));
+        // Only the method names are printed
+        assert_eq!(format!("{:?}", c), "CompoundTask { name: \"loop\", methods: [\"m-again\"] }");
+    }
+
+    #[test]
+    fn method_owner_test() {
+        let task: Rc<Task<u32>> = Rc::new(Task::Compound(CompoundTask::new("loop".to_string(), Vec::new())));
+        let network = HTN::new(HashSet::from([1]), Vec::new(), HashMap::from([(1, Rc::clone(&task))]));
+        if let Task::Compound(c) = task.as_ref() {
+            c.add_method(Method::new("m-again".to_string(), network));
+        }
+        let network = HTN::new(HashSet::from([1]), Vec::new(), HashMap::from([(1, Rc::clone(&task))]))
+            .with_owner(Rc::new(MethodOwner::new([Rc::clone(&task)])));
+        let weak = Rc::downgrade(&task);
+        drop(task);
+        let derived = match network.get_task(1) {
+            Some(Task::Compound(c)) => network.decompose(1, &c.methods()[0]).unwrap(),
+            _ => panic!("`loop` should be compound"),
+        };
+        drop(network);
+        // The derived network keeps the methods alive
+        assert!(matches!(weak.upgrade().as_deref(), Some(Task::Compound(c)) if c.methods().len() == 1));
+        drop(derived);
+        assert!(weak.upgrade().is_none());
     }
 }
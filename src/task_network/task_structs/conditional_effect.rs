@@ -0,0 +1,33 @@
+use std::collections::HashSet;
+use std::hash::Hash;
+
+/// Effects that only take place when their condition holds in the state the
+/// action is applied in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConditionalEffect<T: Eq + Hash> {
+    pub condition: HashSet<T>,
+    /// Facts that must not hold for the effects to take place
+    pub negative_condition: HashSet<T>,
+    pub add_effects: HashSet<T>,
+    pub del_effects: HashSet<T>,
+}
+
+impl<T: Eq + Hash> ConditionalEffect<T> {
+    pub fn new(condition: HashSet<T>, add_effects: HashSet<T>, del_effects: HashSet<T>) -> Self {
+        ConditionalEffect {
+            condition,
+            negative_condition: HashSet::new(),
+            add_effects,
+            del_effects,
+        }
+    }
+
+    pub fn with_negative_condition(mut self, negative_condition: impl IntoIterator<Item = T>) -> Self {
+        self.negative_condition = negative_condition.into_iter().collect();
+        self
+    }
+
+    pub fn holds(&self, state: &HashSet<T>) -> bool {
+        self.condition.is_subset(state) && self.negative_condition.is_disjoint(state)
+    }
+}
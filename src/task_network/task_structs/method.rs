@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::hash::Hash;
 
 use crate::task_network::network::HTN;
@@ -6,13 +7,33 @@ use crate::task_network::network::HTN;
 pub struct Method<T: Hash + Eq> {
     pub name: String,
     pub decomposition: HTN<T>,
+    /// Facts that must hold in the state in which the method is applied
+    pub preconditions: HashSet<T>,
+    /// Added to the cost of a plan each time the method is applied
+    pub cost: u32,
 }
 
 impl<T: Hash + Eq> Method<T> {
     pub fn new(name: String, decomposition: HTN<T>) -> Method<T> {
         Method {
-            name: name,
-            decomposition: decomposition,
+            name,
+            decomposition,
+            preconditions: HashSet::new(),
+            cost: 0,
         }
     }
+
+    pub fn with_preconditions(mut self, preconditions: impl IntoIterator<Item = T>) -> Method<T> {
+        self.preconditions = preconditions.into_iter().collect();
+        self
+    }
+
+    pub fn with_cost(mut self, cost: u32) -> Method<T> {
+        self.cost = cost;
+        self
+    }
+
+    pub fn is_applicable(&self, state: &HashSet<T>) -> bool {
+        self.preconditions.is_subset(state)
+    }
 }
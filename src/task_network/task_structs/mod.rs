@@ -1,9 +1,11 @@
 mod compound_task;
+mod conditional_effect;
 mod method;
 mod primitive_action;
 mod task;
 
-pub use compound_task::CompoundTask;
+pub use compound_task::{CompoundTask, MethodOwner};
+pub use conditional_effect::ConditionalEffect;
 pub use method::Method;
 pub use primitive_action::PrimitiveAction;
 pub use task::Task;
@@ -1,13 +1,17 @@
 use std::{collections::HashSet, hash::Hash};
 
+use super::ConditionalEffect;
 use crate::task_network::applicability::Applicability;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct PrimitiveAction<T: Eq + Hash> {
     pub name: String,
     pre_cond: HashSet<T>,
+    neg_pre_cond: HashSet<T>,
     add_effects: HashSet<T>,
     del_effects: HashSet<T>,
+    conditional_effects: Vec<ConditionalEffect<T>>,
+    cost: u32,
 }
 
 impl<T: Eq + Hash> PrimitiveAction<T> {
@@ -20,10 +24,54 @@ impl<T: Eq + Hash> PrimitiveAction<T> {
         PrimitiveAction {
             name,
             pre_cond,
+            neg_pre_cond: HashSet::new(),
             add_effects,
             del_effects,
+            conditional_effects: Vec::new(),
+            cost: 1,
         }
     }
+
+    /// Sets the facts that must not hold for the action to be applicable.
+    pub fn with_negative_preconditions(mut self, neg_pre_cond: impl IntoIterator<Item = T>) -> Self {
+        self.neg_pre_cond = neg_pre_cond.into_iter().collect();
+        self
+    }
+
+    pub fn with_conditional_effects(mut self, effects: impl IntoIterator<Item = ConditionalEffect<T>>) -> Self {
+        self.conditional_effects = effects.into_iter().collect();
+        self
+    }
+
+    /// Sets the cost of applying the action, which is 1 by default.
+    pub fn with_cost(mut self, cost: u32) -> Self {
+        self.cost = cost;
+        self
+    }
+
+    pub fn cost(&self) -> u32 {
+        self.cost
+    }
+
+    pub fn preconditions(&self) -> &HashSet<T> {
+        &self.pre_cond
+    }
+
+    pub fn negative_preconditions(&self) -> &HashSet<T> {
+        &self.neg_pre_cond
+    }
+
+    pub fn add_effects(&self) -> &HashSet<T> {
+        &self.add_effects
+    }
+
+    pub fn del_effects(&self) -> &HashSet<T> {
+        &self.del_effects
+    }
+
+    pub fn conditional_effects(&self) -> &[ConditionalEffect<T>] {
+        &self.conditional_effects
+    }
 }
 
 impl <U: Eq + Hash> Applicability for PrimitiveAction<U> {
@@ -35,17 +83,27 @@ impl <U: Eq + Hash> Applicability for PrimitiveAction<U> {
                 return false;
             }
         }
+        for condition in self.neg_pre_cond.iter() {
+            if state.contains(condition) {
+                return false;
+            }
+        }
         true
     }
 
+    // Conditions are evaluated on the given state, and all deletes happen
+    // before any add
     fn transition(&self, state: &HashSet<Self::T>) -> HashSet<Self::T>
     where Self::T: Eq + Hash + Clone{
+        let triggered: Vec<&ConditionalEffect<U>> =
+            self.conditional_effects.iter().filter(|e| e.holds(state)).collect();
         let mut new_state: HashSet<Self::T> = state
             .iter()
+            .filter(|x| !self.del_effects.contains(x) && !triggered.iter().any(|e| e.del_effects.contains(x)))
             .cloned()
-            .filter(|x| !self.del_effects.contains(x))
             .collect();
-        for add in self.add_effects.iter() {
+        let adds = self.add_effects.iter().chain(triggered.iter().flat_map(|e| e.add_effects.iter()));
+        for add in adds {
             new_state.insert(add.clone());
         }
         new_state
@@ -66,16 +124,31 @@ mod tests {
             HashSet::from([]),
             HashSet::from([]),
         );
-        assert_eq!(action.is_applicable(&state), true);
+        assert!(action.is_applicable(&state));
         state.insert("is_close");
-        assert_eq!(action.is_applicable(&state), true);
+        assert!(action.is_applicable(&state));
         state.remove("object_visible");
-        assert_eq!(action.is_applicable(&state), false);
+        assert!(!action.is_applicable(&state));
+    }
+
+    #[test]
+    pub fn negative_precondition_test() {
+        let mut state = HashSet::from(["is_loaded"]);
+        let action = PrimitiveAction::new(
+            "Action1".to_string(),
+            HashSet::from(["is_loaded"]),
+            HashSet::from([]),
+            HashSet::from([]),
+        )
+        .with_negative_preconditions(["is_broken"]);
+        assert!(action.is_applicable(&state));
+        state.insert("is_broken");
+        assert!(!action.is_applicable(&state));
     }
 
     #[test]
     pub fn transition_test() {
-        let mut state = HashSet::from(["is_loaded", "object_visible"]);
+        let state = HashSet::from(["is_loaded", "object_visible"]);
         let precond = HashSet::from(["is_loaded", "object_visible"]);
         let action = PrimitiveAction::new(
             "Action1".to_string(),
@@ -84,7 +157,28 @@ mod tests {
             HashSet::from(["is_loaded"]),
         );
         let new_state = action.transition(&state);
-        assert_eq!(new_state.contains("ready"), true);
+        assert!(new_state.contains("ready"));
         assert_eq!(new_state.len(), 2);
     }
+
+    #[test]
+    pub fn conditional_effect_test() {
+        let action = PrimitiveAction::new(
+            "Open".to_string(),
+            HashSet::from([]),
+            HashSet::from(["open"]),
+            HashSet::from(["closed"]),
+        )
+        .with_conditional_effects([
+            ConditionalEffect::new(HashSet::from(["armed"]), HashSet::from(["alarm"]), HashSet::from([])),
+            // Evaluated on the state before the action
+            ConditionalEffect::new(HashSet::from(["open"]), HashSet::from([]), HashSet::from(["draft"])),
+            // Deletes happen before adds
+            ConditionalEffect::new(HashSet::from([]), HashSet::from([]), HashSet::from(["open"])),
+        ]);
+        let new_state = action.transition(&HashSet::from(["closed", "armed", "draft"]));
+        assert_eq!(new_state, HashSet::from(["open", "armed", "alarm", "draft"]));
+        let new_state = action.transition(&HashSet::from(["open", "draft"]));
+        assert_eq!(new_state, HashSet::from(["open"]));
+    }
 }
@@ -25,10 +25,6 @@ impl<T: Eq + Hash> PartialEq for Task<T> {
             },
         }
     }
-
-    fn ne(&self, other: &Self) -> bool {
-        !self.eq(&other)
-    }
 }
 
 impl<T: Eq + Hash> Eq for Task<T> {}
@@ -0,0 +1,68 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::task_network::HtnError;
+
+/// The first reason a plan is not a valid refinement of the initial network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerificationError {
+    UnknownAction { step: usize, action: String },
+    NotExecutable { step: usize, action: String },
+    /// No refinement of the initial network produces the actions up to `step`
+    NotDerivable { step: usize, action: String },
+    /// The actions can be derived but tasks remain that cannot be refined away
+    UnrefinedTasks,
+    UndefinedInstance(u32),
+    DuplicateInstance(u32),
+    UnusedInstance(u32),
+    UnknownTask { id: u32, task: String },
+    UnknownMethod { id: u32, task: String, method: String },
+    /// The subtasks of a decomposition do not match its method
+    SubtaskMismatch { id: u32 },
+    /// The preconditions of the method hold in no state before its first action
+    MethodNotApplicable { id: u32 },
+    /// The root tasks do not match the initial network
+    RootMismatch,
+    LimitReached,
+    /// A task network met while refining the initial one is malformed
+    InvalidNetwork(HtnError),
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerificationError::UnknownAction { step, action } => {
+                write!(f, "step {}: unknown action `{}`", step, action)
+            }
+            VerificationError::NotExecutable { step, action } => {
+                write!(f, "step {}: `{}` is not applicable", step, action)
+            }
+            VerificationError::NotDerivable { step, action } => {
+                write!(f, "step {}: `{}` cannot be derived from the task network", step, action)
+            }
+            VerificationError::UnrefinedTasks => write!(f, "the task network cannot be fully refined"),
+            VerificationError::UndefinedInstance(id) => write!(f, "task {} is not defined", id),
+            VerificationError::DuplicateInstance(id) => write!(f, "task {} is used more than once", id),
+            VerificationError::UnusedInstance(id) => write!(f, "task {} is not reachable from the root", id),
+            VerificationError::UnknownTask { id, task } => {
+                write!(f, "task {}: `{}` is not a compound task", id, task)
+            }
+            VerificationError::UnknownMethod { id, task, method } => {
+                write!(f, "task {}: `{}` has no method `{}`", id, task, method)
+            }
+            VerificationError::SubtaskMismatch { id } => {
+                write!(f, "task {}: the subtasks or their order do not match the method", id)
+            }
+            VerificationError::MethodNotApplicable { id } => {
+                write!(f, "task {}: the preconditions of the method do not hold", id)
+            }
+            VerificationError::RootMismatch => {
+                write!(f, "the root tasks or their order do not match the initial network")
+            }
+            VerificationError::LimitReached => write!(f, "verification limit reached"),
+            VerificationError::InvalidNetwork(e) => write!(f, "invalid task network: {}", e),
+        }
+    }
+}
+
+impl Error for VerificationError {}
@@ -0,0 +1,5 @@
+mod error;
+mod verifier;
+
+pub use error::VerificationError;
+pub use verifier::{PlanVerifier, DEFAULT_MAX_EXPANSIONS};
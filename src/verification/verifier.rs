@@ -0,0 +1,606 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+use std::rc::Rc;
+
+use super::error::VerificationError;
+use crate::plan::{Decomposition, Plan};
+use crate::task_network::{Applicability, Method, Task, HTN};
+
+/// Checks plans against an initial state and task network.
+pub struct PlanVerifier<'a, T: Hash + Eq> {
+    initial_state: &'a HashSet<T>,
+    initial_network: &'a HTN<T>,
+    tasks: HashMap<String, Rc<Task<T>>>,
+    max_expansions: usize,
+}
+
+/// Task networks explored by `verify_sequence` before giving up, unless set
+/// otherwise with `set_max_expansions`.
+pub const DEFAULT_MAX_EXPANSIONS: usize = 1_000_000;
+
+enum Instance<'p> {
+    Action(usize, &'p str),
+    Compound(&'p Decomposition),
+}
+
+impl<'p> Instance<'p> {
+    fn name(&self) -> &'p str {
+        match self {
+            Instance::Action(_, name) => name,
+            Instance::Compound(d) => &d.task,
+        }
+    }
+}
+
+// Position of the first and last action below each task instance, if any
+type Spans = HashMap<u32, Option<(usize, usize)>>;
+
+impl<'a, T: Hash + Eq + Clone> PlanVerifier<'a, T> {
+    pub fn new(initial_state: &'a HashSet<T>, initial_network: &'a HTN<T>) -> PlanVerifier<'a, T> {
+        PlanVerifier {
+            initial_state,
+            initial_network,
+            tasks: initial_network.get_reachable_tasks(),
+            max_expansions: DEFAULT_MAX_EXPANSIONS,
+        }
+    }
+
+    /// Bounds the number of task networks explored when no decomposition
+    /// tree is given. Networks met before are skipped, but recursive methods
+    /// can still grow networks without end.
+    pub fn set_max_expansions(&mut self, limit: usize) {
+        self.max_expansions = limit;
+    }
+
+    /// Checks that the actions are executable in order and that some
+    /// refinement of the initial network yields exactly this sequence.
+    pub fn verify_sequence(&self, actions: &[String]) -> Result<(), VerificationError> {
+        let states = self.check_executable(actions)?;
+        self.derive(actions, &states)
+    }
+
+    /// Checks an action sequence together with its decomposition tree.
+    /// Methods must be applicable once their task has no predecessors left,
+    /// which is when progression search decomposes it.
+    /// Plans without a root are verified as plain action sequences.
+    pub fn verify_plan(&self, plan: &Plan) -> Result<(), VerificationError> {
+        let actions = plan.action_names();
+        if plan.root.is_empty() && plan.decompositions.is_empty() {
+            return self.verify_sequence(&actions);
+        }
+        let states = self.check_executable(&actions)?;
+
+        let mut instances: HashMap<u32, Instance> = HashMap::new();
+        let defined = plan
+            .actions
+            .iter()
+            .enumerate()
+            .map(|(step, a)| (a.id, Instance::Action(step, &a.name)))
+            .chain(plan.decompositions.iter().map(|d| (d.id, Instance::Compound(d))));
+        for (id, instance) in defined {
+            if instances.insert(id, instance).is_some() {
+                return Err(VerificationError::DuplicateInstance(id));
+            }
+        }
+        let mut spans = Spans::new();
+        for id in plan.root.iter() {
+            self.span(*id, &instances, &mut spans)?;
+        }
+        if let Some(id) = instances.keys().find(|x| !spans.contains_key(x)) {
+            return Err(VerificationError::UnusedInstance(*id));
+        }
+
+        let Some(assignment) = self.matches(self.initial_network, &plan.root, &instances, &spans) else {
+            return Err(VerificationError::RootMismatch);
+        };
+        // Step at which each instance is refined. Like the search, compound
+        // tasks are decomposed as soon as the tasks ordered before them, and
+        // before their ancestors, are done, so methods are checked there.
+        let mut ready = HashMap::new();
+        let mut open = VecDeque::new();
+        release(self.initial_network, &assignment, 0, &spans, &mut ready, &mut open);
+        while let Some(id) = open.pop_front() {
+            let Some(Instance::Compound(d)) = instances.get(&id) else {
+                continue;
+            };
+            let compound = match self.tasks.get(&d.task).map(|x| x.as_ref()) {
+                Some(Task::Compound(c)) => c,
+                _ => {
+                    return Err(VerificationError::UnknownTask {
+                        id: d.id,
+                        task: d.task.clone(),
+                    })
+                }
+            };
+            let methods = compound.methods();
+            let candidates: Vec<&Method<T>> = methods.iter().filter(|m| m.name == d.method).collect();
+            if candidates.is_empty() {
+                return Err(VerificationError::UnknownMethod {
+                    id: d.id,
+                    task: d.task.clone(),
+                    method: d.method.clone(),
+                });
+            }
+            let matching: Vec<(&Method<T>, HashMap<u32, u32>)> = candidates
+                .into_iter()
+                .filter_map(|m| Some((m, self.matches(&m.decomposition, &d.subtasks, &instances, &spans)?)))
+                .collect();
+            if matching.is_empty() {
+                return Err(VerificationError::SubtaskMismatch { id: d.id });
+            }
+            let state = &states[ready[&d.id]];
+            let Some((method, assignment)) = matching.iter().find(|(m, _)| m.is_applicable(state)) else {
+                return Err(VerificationError::MethodNotApplicable { id: d.id });
+            };
+            release(&method.decomposition, assignment, ready[&d.id], &spans, &mut ready, &mut open);
+        }
+        Ok(())
+    }
+
+    // The states before each action and after the last one
+    fn check_executable(&self, actions: &[String]) -> Result<Vec<HashSet<T>>, VerificationError> {
+        let mut state = self.initial_state.clone();
+        let mut states = Vec::with_capacity(actions.len() + 1);
+        for (step, name) in actions.iter().enumerate() {
+            match self.tasks.get(name).map(|x| x.as_ref()) {
+                Some(Task::Primitive(a)) => {
+                    if !a.is_applicable(&state) {
+                        return Err(VerificationError::NotExecutable {
+                            step,
+                            action: name.clone(),
+                        });
+                    }
+                    let next = a.transition(&state);
+                    states.push(state);
+                    state = next;
+                }
+                _ => {
+                    return Err(VerificationError::UnknownAction {
+                        step,
+                        action: name.clone(),
+                    })
+                }
+            }
+        }
+        states.push(state);
+        Ok(states)
+    }
+
+    // Progression restricted to the given actions: compound tasks are
+    // decomposed first, and only the next action of the sequence is applied.
+    fn derive(&self, actions: &[String], states: &[HashSet<T>]) -> Result<(), VerificationError> {
+        let mut fringe = VecDeque::from([(self.initial_network.clone(), 0)]);
+        let mut furthest = 0;
+        // Furthest step at which refinements were cut for needing more actions
+        let mut truncated = None;
+        let mut expansions = 0;
+        // The state is given by the step, so only networks are told apart:
+        // by their hash first, and exactly on a match
+        let mut seen: HashMap<(usize, u64), Vec<HTN<T>>> = HashMap::new();
+        while let Some((network, step)) = fringe.pop_front() {
+            if network.count_tasks() == 0 && step == actions.len() {
+                return Ok(());
+            }
+            let same = seen.entry((step, network.canonical_hash())).or_default();
+            if same.iter().any(|x| x.is_equivalent(&network)) {
+                continue;
+            }
+            same.push(network.clone());
+            expansions += 1;
+            if expansions > self.max_expansions {
+                return Err(VerificationError::LimitReached);
+            }
+            furthest = furthest.max(step);
+            let mut unconstrained: Vec<u32> = network.get_unconstrained_tasks().into_iter().collect();
+            unconstrained.sort();
+            let mut compound = None;
+            for id in unconstrained.iter() {
+                if !network.is_primitive(*id).map_err(VerificationError::InvalidNetwork)? {
+                    compound = Some(id);
+                    break;
+                }
+            }
+            if let Some(id) = compound {
+                if let Some(Task::Compound(c)) = network.get_task(*id) {
+                    for m in c.methods().iter().filter(|m| m.is_applicable(&states[step])) {
+                        let successor = network.decompose(*id, m).map_err(VerificationError::InvalidNetwork)?;
+                        // Tasks only leave the network by being applied
+                        if count_primitive(&successor) <= actions.len() - step {
+                            fringe.push_back((successor, step));
+                        } else {
+                            truncated = truncated.max(Some(step));
+                        }
+                    }
+                }
+            } else if step < actions.len() {
+                for id in unconstrained {
+                    if network.get_task(id).map(|x| x.get_name()).as_ref() == Some(&actions[step]) {
+                        let successor = network.apply_action(id).map_err(VerificationError::InvalidNetwork)?;
+                        fringe.push_back((successor, step + 1));
+                    }
+                }
+            }
+        }
+        if furthest == actions.len() || truncated == Some(furthest) {
+            Err(VerificationError::UnrefinedTasks)
+        } else {
+            Err(VerificationError::NotDerivable {
+                step: furthest,
+                action: actions[furthest].clone(),
+            })
+        }
+    }
+
+    fn span(&self, id: u32, instances: &HashMap<u32, Instance>, spans: &mut Spans) -> Result<Option<(usize, usize)>, VerificationError> {
+        if spans.contains_key(&id) {
+            return Err(VerificationError::DuplicateInstance(id));
+        }
+        // Mark as visited before descending, so that cycles are reported as duplicates
+        spans.insert(id, None);
+        let span = match instances.get(&id) {
+            None => return Err(VerificationError::UndefinedInstance(id)),
+            Some(Instance::Action(step, _)) => Some((*step, *step)),
+            Some(Instance::Compound(d)) => {
+                let mut span: Option<(usize, usize)> = None;
+                for child in d.subtasks.iter() {
+                    if let Some((first, last)) = self.span(*child, instances, spans)? {
+                        span = Some(match span {
+                            Some((a, b)) => (a.min(first), b.max(last)),
+                            None => (first, last),
+                        });
+                    }
+                }
+                span
+            }
+        };
+        spans.insert(id, span);
+        Ok(span)
+    }
+
+    // Whether the instances can be matched one-to-one with the network's
+    // tasks by name, such that every ordering of the network is respected by
+    // the actions below the matched instances.
+    // The instance each task of the network stands for, if they match
+    fn matches(&self, network: &HTN<T>, instances: &[u32], table: &HashMap<u32, Instance>, spans: &Spans) -> Option<HashMap<u32, u32>> {
+        let mut ids: Vec<u32> = network.get_task_ids().into_iter().collect();
+        if ids.len() != instances.len() {
+            return None;
+        }
+        ids.sort();
+        let orderings = network.get_orderings();
+        let mut assignment: HashMap<u32, u32> = HashMap::new();
+        let mut used = vec![false; instances.len()];
+        self.assign(network, &ids, instances, table, spans, &orderings, &mut assignment, &mut used)
+            .then_some(assignment)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn assign(
+        &self,
+        network: &HTN<T>,
+        ids: &[u32],
+        instances: &[u32],
+        table: &HashMap<u32, Instance>,
+        spans: &Spans,
+        orderings: &[(u32, u32)],
+        assignment: &mut HashMap<u32, u32>,
+        used: &mut [bool],
+    ) -> bool {
+        let Some((id, rest)) = ids.split_first() else {
+            return orderings.iter().all(|(a, b)| {
+                match (spans[&assignment[a]], spans[&assignment[b]]) {
+                    (Some((_, last)), Some((first, _))) => last < first,
+                    _ => true,
+                }
+            });
+        };
+        let Some(name) = network.get_task(*id).map(|x| x.get_name()) else {
+            return false;
+        };
+        for (i, instance) in instances.iter().enumerate() {
+            if used[i] || table[instance].name() != name {
+                continue;
+            }
+            used[i] = true;
+            assignment.insert(*id, *instance);
+            if self.assign(network, rest, instances, table, spans, orderings, assignment, used) {
+                return true;
+            }
+            used[i] = false;
+        }
+        false
+    }
+}
+
+// Records when the instances assigned to the network's tasks can be refined
+// at the earliest, given that their parent can be from `base` on
+fn release<T: Hash + Eq>(
+    network: &HTN<T>,
+    assignment: &HashMap<u32, u32>,
+    base: usize,
+    spans: &Spans,
+    ready: &mut HashMap<u32, usize>,
+    open: &mut VecDeque<u32>,
+) {
+    let orderings = network.get_orderings();
+    for (id, instance) in assignment.iter() {
+        let mut earliest = base;
+        // Tasks ordered before this one, directly or not
+        let mut stack = vec![*id];
+        let mut visited = HashSet::new();
+        while let Some(x) = stack.pop() {
+            for (a, _) in orderings.iter().filter(|(_, b)| *b == x) {
+                if visited.insert(*a) {
+                    if let Some((_, last)) = spans[&assignment[a]] {
+                        earliest = earliest.max(last + 1);
+                    }
+                    stack.push(*a);
+                }
+            }
+        }
+        ready.insert(*instance, earliest);
+        open.push_back(*instance);
+    }
+}
+
+fn count_primitive<T: Hash + Eq>(network: &HTN<T>) -> usize {
+    network
+        .get_task_ids()
+        .into_iter()
+        .filter(|x| matches!(network.get_task(*x), Some(Task::Primitive(_))))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::{DomainBuilder, NetworkBuilder};
+    use crate::example::{create_problem_instance, create_state_problem_instance};
+    use crate::plan::PlanAction;
+    use crate::search::{ProgressionSearch, SearchResult};
+
+    fn solve<T: Hash + Eq + Clone + std::fmt::Debug>(network: HTN<T>) -> Plan {
+        match ProgressionSearch::new().run(HashSet::new(), network).unwrap() {
+            SearchResult::Solved { plan, .. } => plan,
+            x => panic!("expected a solution, got {:?}", x),
+        }
+    }
+
+    #[test]
+    fn valid_sequence_test() {
+        let network = create_problem_instance();
+        let plan = solve(network.clone());
+        let state = HashSet::new();
+        let verifier = PlanVerifier::new(&state, &network);
+        assert_eq!(verifier.verify_sequence(&plan.action_names()), Ok(()));
+        assert_eq!(verifier.verify_plan(&plan), Ok(()));
+    }
+
+    #[test]
+    fn invalid_sequence_test() {
+        let network = create_state_problem_instance();
+        let state = HashSet::new();
+        let verifier = PlanVerifier::new(&state, &network);
+        let mut actions = solve(network.clone()).action_names();
+
+        actions.swap(2, 3);
+        assert_eq!(
+            verifier.verify_sequence(&actions),
+            Err(VerificationError::NotDerivable { step: 2, action: "BuildFrame".to_string() })
+        );
+        actions.swap(2, 3);
+        actions.swap(0, 1);
+        assert_eq!(
+            verifier.verify_sequence(&actions),
+            Err(VerificationError::NotExecutable { step: 0, action: "HireBuilder".to_string() })
+        );
+        actions.swap(0, 1);
+        actions.pop();
+        assert_eq!(verifier.verify_sequence(&actions), Err(VerificationError::UnrefinedTasks));
+        actions.push("Demolish".to_string());
+        assert_eq!(
+            verifier.verify_sequence(&actions),
+            Err(VerificationError::UnknownAction { step: 7, action: "Demolish".to_string() })
+        );
+    }
+
+    #[test]
+    fn invalid_tree_test() {
+        let network = create_problem_instance();
+        let state = HashSet::new();
+        let verifier = PlanVerifier::new(&state, &network);
+        let plan = solve(network.clone());
+
+        let mut wrong_method = plan.clone();
+        wrong_method.decompositions[0].method = "method-02".to_string();
+        assert!(matches!(
+            verifier.verify_plan(&wrong_method),
+            Err(VerificationError::UnknownMethod { id: 3, .. })
+        ));
+
+        let mut reordered = plan.clone();
+        reordered.actions.swap(2, 3);
+        assert_eq!(verifier.verify_plan(&reordered), Err(VerificationError::SubtaskMismatch { id: 3 }));
+
+        let mut reordered_root = plan.clone();
+        reordered_root.actions.swap(6, 7);
+        assert_eq!(verifier.verify_plan(&reordered_root), Err(VerificationError::RootMismatch));
+
+        let mut missing = plan.clone();
+        missing.decompositions[0].subtasks.push(42);
+        assert_eq!(verifier.verify_plan(&missing), Err(VerificationError::UndefinedInstance(42)));
+
+        let mut unused = plan;
+        unused.root.pop();
+        assert_eq!(verifier.verify_plan(&unused), Err(VerificationError::UnusedInstance(4)));
+    }
+
+    #[test]
+    fn method_precondition_test() {
+        let network = DomainBuilder::new()
+            .primitive("drive", [], [], [])
+            .compound("go")
+            .method("go", "m-drive", NetworkBuilder::new().task("d", "drive"))
+            .method_preconditions("go", "m-drive", ["car"])
+            .build(NetworkBuilder::new().task("g", "go"))
+            .unwrap();
+        let with_car = HashSet::from(["car"]);
+        let plan = match ProgressionSearch::new().run(with_car.clone(), network.clone()).unwrap() {
+            SearchResult::Solved { plan, .. } => plan,
+            x => panic!("expected a solution, got {:?}", x),
+        };
+        assert_eq!(PlanVerifier::new(&with_car, &network).verify_plan(&plan), Ok(()));
+
+        let state = HashSet::new();
+        let verifier = PlanVerifier::new(&state, &network);
+        assert_eq!(verifier.verify_plan(&plan), Err(VerificationError::MethodNotApplicable { id: 1 }));
+        assert!(matches!(
+            verifier.verify_sequence(&plan.action_names()),
+            Err(VerificationError::NotDerivable { step: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn method_precondition_window_test() {
+        let network = DomainBuilder::new()
+            .primitive("drive", [], [], [])
+            .primitive("del-car", [], [], ["car"])
+            .compound("go")
+            .method("go", "m-drive", NetworkBuilder::new().task("d", "drive"))
+            .method_preconditions("go", "m-drive", ["car"])
+            .build(NetworkBuilder::new().task("x", "del-car").task("g", "go").before("x", "g"))
+            .unwrap();
+        let state = HashSet::from(["car"]);
+        let verifier = PlanVerifier::new(&state, &network);
+        let action = |id, name: &str| PlanAction { id, name: name.to_string() };
+        // `car` only holds before `del-car`, which must come before `go`
+        let plan = Plan::new(
+            Vec::from([action(1, "del-car"), action(3, "drive")]),
+            Vec::from([1, 2]),
+            Vec::from([Decomposition {
+                id: 2,
+                task: "go".to_string(),
+                method: "m-drive".to_string(),
+                subtasks: Vec::from([3]),
+            }]),
+        );
+        assert_eq!(verifier.verify_plan(&plan), Err(VerificationError::MethodNotApplicable { id: 2 }));
+        assert!(matches!(
+            verifier.verify_sequence(&plan.action_names()),
+            Err(VerificationError::NotDerivable { .. })
+        ));
+        assert!(matches!(
+            ProgressionSearch::new().run(state.clone(), network.clone()).unwrap(),
+            SearchResult::Unsolvable
+        ));
+    }
+
+    #[test]
+    fn method_precondition_order_test() {
+        let domain = || {
+            DomainBuilder::new()
+                .primitive("drive", [], [], [])
+                .primitive("get-car", [], ["car"], [])
+                .compound("go")
+                .method("go", "m-drive", NetworkBuilder::new().task("d", "drive"))
+                .method_preconditions("go", "m-drive", ["car"])
+        };
+        let action = |id, name: &str| PlanAction { id, name: name.to_string() };
+        // `go` is decomposed before any action unless ordered after `get-car`
+        let plan = Plan::new(
+            Vec::from([action(1, "get-car"), action(3, "drive")]),
+            Vec::from([1, 2]),
+            Vec::from([Decomposition {
+                id: 2,
+                task: "go".to_string(),
+                method: "m-drive".to_string(),
+                subtasks: Vec::from([3]),
+            }]),
+        );
+        let state = HashSet::new();
+        let unordered = domain()
+            .build(NetworkBuilder::new().task("c", "get-car").task("g", "go"))
+            .unwrap();
+        let verifier = PlanVerifier::new(&state, &unordered);
+        assert_eq!(verifier.verify_plan(&plan), Err(VerificationError::MethodNotApplicable { id: 2 }));
+        assert_eq!(
+            verifier.verify_sequence(&plan.action_names()),
+            Err(VerificationError::NotDerivable { step: 0, action: "get-car".to_string() })
+        );
+        assert!(matches!(
+            ProgressionSearch::new().run(state.clone(), unordered.clone()).unwrap(),
+            SearchResult::Unsolvable
+        ));
+
+        let ordered = domain()
+            .build(NetworkBuilder::new().task("c", "get-car").task("g", "go").before("c", "g"))
+            .unwrap();
+        let verifier = PlanVerifier::new(&state, &ordered);
+        assert_eq!(verifier.verify_plan(&plan), Ok(()));
+        assert_eq!(verifier.verify_sequence(&plan.action_names()), Ok(()));
+        let found = match ProgressionSearch::new().run(state.clone(), ordered.clone()).unwrap() {
+            SearchResult::Solved { plan, .. } => plan,
+            x => panic!("expected a solution, got {:?}", x),
+        };
+        assert_eq!(found.action_names(), plan.action_names());
+        assert_eq!(verifier.verify_plan(&found), Ok(()));
+    }
+
+    #[test]
+    fn hash_collision_test() {
+        let tasks = || NetworkBuilder::new().task("t1", "a").task("t2", "b").task("t3", "c").task("t4", "c");
+        let m1 = ("m1", tasks().before("t1", "t3").before("t2", "t3"));
+        let m2 = ("m2", tasks().before("t1", "t3").before("t2", "t4"));
+        for methods in [[m1.clone(), m2.clone()], [m2, m1]] {
+            let mut domain = DomainBuilder::new()
+                .primitive("a", ["q"], [], [])
+                .primitive("b", ["q"], [], [])
+                .primitive("c", [], ["q"], [])
+                .compound("x");
+            for (name, network) in methods {
+                domain = domain.method("x", name, network);
+            }
+            let network = domain.build(NetworkBuilder::new().task("x", "x")).unwrap();
+            let state = HashSet::new();
+            let verifier = PlanVerifier::new(&state, &network);
+            for plan in [["c", "a", "b", "c"], ["c", "b", "a", "c"]] {
+                let actions: Vec<String> = plan.iter().map(|x| x.to_string()).collect();
+                assert_eq!(verifier.verify_sequence(&actions), Ok(()));
+            }
+        }
+    }
+
+    #[test]
+    fn recursive_sequence_test() {
+        let domain = || {
+            DomainBuilder::<u32>::new()
+                .primitive("b", [], [], [])
+                .compound("spin")
+                .method("spin", "m-again", NetworkBuilder::new().task("s", "spin"))
+                .method("spin", "m-done", NetworkBuilder::new().task("b", "b"))
+        };
+        let network = domain().build(NetworkBuilder::new().task("s", "spin")).unwrap();
+        let state = HashSet::new();
+        let verifier = PlanVerifier::new(&state, &network);
+        let actions = vec!["b".to_string(), "b".to_string()];
+        assert_eq!(
+            verifier.verify_sequence(&actions),
+            Err(VerificationError::NotDerivable { step: 1, action: "b".to_string() })
+        );
+        assert_eq!(verifier.verify_sequence(&actions[..1]), Ok(()));
+
+        // Networks that keep growing are cut off by the expansion bound
+        let network = domain()
+            .primitive("a", [], [], [])
+            .method("spin", "m-split", NetworkBuilder::new().task("x", "spin").task("y", "spin"))
+            .method("spin", "m-none", NetworkBuilder::new())
+            .build(NetworkBuilder::new().task("s", "spin").task("a", "a").before("s", "a"))
+            .unwrap();
+        let mut verifier = PlanVerifier::new(&state, &network);
+        verifier.set_max_expansions(100);
+        let actions = vec!["a".to_string(), "b".to_string()];
+        assert_eq!(verifier.verify_sequence(&actions), Err(VerificationError::LimitReached));
+        let actions = vec!["b".to_string(), "b".to_string(), "a".to_string()];
+        assert_eq!(verifier.verify_sequence(&actions), Ok(()));
+    }
+}